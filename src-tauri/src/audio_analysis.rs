@@ -0,0 +1,334 @@
+use serde::Serialize;
+use std::ops::Range;
+
+/// Fraction of samples whose absolute amplitude is below the threshold.
+/// An empty buffer counts as entirely silent.
+pub fn silence_ratio(samples: &[f32], threshold: f32) -> f32 {
+    if samples.is_empty() {
+        return 1.0;
+    }
+
+    let silent = samples.iter().filter(|s| s.abs() < threshold).count();
+    silent as f32 / samples.len() as f32
+}
+
+// Rates audio hardware and codecs actually produce
+const COMMON_SAMPLE_RATES: &[u32] = &[
+    8000, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 96000, 176400, 192000,
+];
+
+/// Sanity-check a claimed sample rate against the buffer it describes.
+/// Returns human-readable warnings; an empty list means nothing looked off.
+pub fn sample_rate_warnings(sample_count: usize, sample_rate: u32) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if !(8000..=192000).contains(&sample_rate) {
+        warnings.push(format!(
+            "Sample rate {} Hz is outside the usual 8-192 kHz range for speech audio",
+            sample_rate
+        ));
+    } else if !COMMON_SAMPLE_RATES.contains(&sample_rate) {
+        warnings.push(format!(
+            "Sample rate {} Hz is not a standard audio rate",
+            sample_rate
+        ));
+    }
+
+    let duration = sample_count as f32 / sample_rate as f32;
+    if duration < 0.25 {
+        warnings.push(format!(
+            "Audio is only {:.2}s long at {} Hz; the claimed rate may be too high",
+            duration, sample_rate
+        ));
+    } else if duration > 4.0 * 60.0 * 60.0 {
+        warnings.push(format!(
+            "Audio is {:.1} hours long at {} Hz; the claimed rate may be too low",
+            duration / 3600.0,
+            sample_rate
+        ));
+    }
+
+    warnings
+}
+
+// Below this peak a decoded file is treated as silent
+const MEANINGFUL_PEAK: f32 = 0.01;
+
+pub fn peak_amplitude(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()))
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
+    (sum_squares / samples.len() as f32).sqrt()
+}
+
+/// Whether a buffer contains anything louder than near-silence
+pub fn has_meaningful_audio(samples: &[f32]) -> bool {
+    peak_amplitude(samples) >= MEANINGFUL_PEAK
+}
+
+// A run must be at least this long at the clip level to count as flat-topped
+const MIN_CLIP_RUN: usize = 2;
+// Quieter audio can't be clipped in a way worth repairing
+const MIN_CLIP_LEVEL: f32 = 0.3;
+
+#[derive(Serialize, Clone, Default)]
+pub struct ClipStats {
+    pub clipped_samples: usize,
+    pub clipped_runs: usize,
+    pub repaired_samples: usize,
+    pub clip_ratio: f32,
+}
+
+/// Find flat-topped runs sitting at the buffer's peak level, as [start, end) ranges
+fn find_clipped_runs(samples: &[f32]) -> Vec<(usize, usize)> {
+    let peak = peak_amplitude(samples);
+    if peak < MIN_CLIP_LEVEL {
+        return Vec::new();
+    }
+
+    let level = peak * 0.995;
+    let mut runs = Vec::new();
+    let mut i = 0;
+
+    while i < samples.len() {
+        if samples[i].abs() < level {
+            i += 1;
+            continue;
+        }
+
+        let sign = samples[i].signum();
+        let start = i;
+        while i < samples.len() && samples[i].abs() >= level && samples[i].signum() == sign {
+            i += 1;
+        }
+
+        if i - start >= MIN_CLIP_RUN {
+            runs.push((start, i));
+        }
+    }
+
+    runs
+}
+
+/// Report clipping without modifying the samples
+pub fn detect_clipping(samples: &[f32]) -> ClipStats {
+    let runs = find_clipped_runs(samples);
+    let clipped_samples: usize = runs.iter().map(|(start, end)| end - start).sum();
+
+    ClipStats {
+        clipped_samples,
+        clipped_runs: runs.len(),
+        repaired_samples: 0,
+        clip_ratio: if samples.is_empty() {
+            0.0
+        } else {
+            clipped_samples as f32 / samples.len() as f32
+        },
+    }
+}
+
+/// Rebuild clipped runs with a cubic through the two samples either side of each run
+pub fn declip(samples: &mut [f32]) -> ClipStats {
+    let mut stats = detect_clipping(samples);
+
+    for (start, end) in find_clipped_runs(samples) {
+        // Runs at the very edges have no anchors on one side
+        if start < 2 || end + 2 > samples.len() {
+            continue;
+        }
+
+        let xs = [start - 2, start - 1, end, end + 1].map(|x| x as f32);
+        let ys = [
+            samples[start - 2],
+            samples[start - 1],
+            samples[end],
+            samples[end + 1],
+        ];
+
+        for (i, sample) in samples.iter_mut().enumerate().take(end).skip(start) {
+            let x = i as f32;
+
+            // Lagrange form of the cubic through the four anchors
+            let value: f32 = (0..4)
+                .map(|k| {
+                    (0..4)
+                        .filter(|&j| j != k)
+                        .fold(ys[k], |term, j| term * (x - xs[j]) / (xs[k] - xs[j]))
+                })
+                .sum();
+
+            *sample = value.clamp(-1.0, 1.0);
+            stats.repaired_samples += 1;
+        }
+    }
+
+    stats
+}
+
+/// Peak absolute amplitude per bucket, for drawing a waveform overview
+pub fn waveform_peaks(samples: &[f32], buckets: usize) -> Vec<f32> {
+    if samples.is_empty() || buckets == 0 {
+        return Vec::new();
+    }
+
+    let bucket_len = samples.len().div_ceil(buckets);
+    samples.chunks(bucket_len).map(peak_amplitude).collect()
+}
+
+// Aim for roughly -20 dBFS RMS, with headroom so peaks stay below clipping
+const TARGET_RMS: f32 = 0.1;
+const MAX_PEAK_AFTER_GAIN: f32 = 0.9;
+const CLIP_WARNING_PEAK: f32 = 0.99;
+
+#[derive(Serialize)]
+pub struct InputCalibration {
+    pub peak: f32,
+    pub rms: f32,
+    pub suggested_gain: f32,
+    pub clipping: bool,
+}
+
+/// Measure a calibration capture and suggest a software gain that brings it to the
+/// target level without pushing peaks into clipping
+pub fn calibrate_levels(samples: &[f32]) -> InputCalibration {
+    let peak = peak_amplitude(samples);
+    let rms = rms(samples);
+
+    let suggested_gain = if rms > 0.0 && peak > 0.0 {
+        (TARGET_RMS / rms)
+            .min(MAX_PEAK_AFTER_GAIN / peak)
+            .clamp(0.1, 10.0)
+    } else {
+        1.0
+    };
+
+    InputCalibration {
+        peak,
+        rms,
+        suggested_gain,
+        clipping: peak >= CLIP_WARNING_PEAK,
+    }
+}
+
+pub const DEFAULT_SILENCE_RMS: f32 = 0.01;
+
+/// Tracks how long a live signal has stayed quiet after speech was first heard,
+/// so a recording can stop itself once the speaker is done
+pub struct SilenceTracker {
+    threshold: f32,
+    timeout: std::time::Duration,
+    heard_speech: bool,
+    silent_for: std::time::Duration,
+}
+
+impl SilenceTracker {
+    pub fn new(threshold: f32, timeout: std::time::Duration) -> Self {
+        Self {
+            threshold,
+            timeout,
+            heard_speech: false,
+            silent_for: std::time::Duration::ZERO,
+        }
+    }
+
+    /// Feed the level measured over the last `elapsed`. Returns true once the signal
+    /// has been below the threshold for the timeout; leading silence never counts.
+    pub fn update(&mut self, level: f32, elapsed: std::time::Duration) -> bool {
+        if level >= self.threshold {
+            self.heard_speech = true;
+            self.silent_for = std::time::Duration::ZERO;
+            return false;
+        }
+
+        if !self.heard_speech {
+            return false;
+        }
+
+        self.silent_for += elapsed;
+        self.silent_for >= self.timeout
+    }
+}
+
+// Window the trim level is measured over, and audio kept either side of the loud
+// region so word onsets and tails aren't clipped (both at 16kHz)
+const TRIM_WINDOW: usize = 320;
+const TRIM_PADDING: usize = 3200;
+
+/// Range of samples left after cutting leading and trailing audio whose windowed
+/// RMS stays below threshold, keeping a little padding. A clip that never gets
+/// louder keeps its full range.
+pub fn speech_bounds(samples: &[f32], threshold: f32) -> Range<usize> {
+    let is_loud = |window: &[f32]| rms(window) >= threshold;
+
+    let first = samples.chunks(TRIM_WINDOW).position(is_loud);
+    let last = samples.chunks(TRIM_WINDOW).rposition(is_loud);
+
+    match (first, last) {
+        (Some(first), Some(last)) => {
+            let start = (first * TRIM_WINDOW).saturating_sub(TRIM_PADDING);
+            let end = ((last + 1) * TRIM_WINDOW + TRIM_PADDING).min(samples.len());
+            start..end
+        }
+        _ => 0..samples.len(),
+    }
+}
+
+/// Slice off leading and trailing silence (see speech_bounds)
+pub fn trim_silence(samples: &[f32], threshold: f32) -> &[f32] {
+    &samples[speech_bounds(samples, threshold)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rms_of_a_known_buffer() {
+        assert_eq!(rms(&[]), 0.0);
+        assert!((rms(&[0.5, -0.5, 0.5, -0.5]) - 0.5).abs() < 1e-6);
+        assert!((rms(&[0.3, -0.4]) - 0.125f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn silence_tracker_stops_after_speech_then_silence() {
+        let step = std::time::Duration::from_millis(100);
+        let mut tracker = SilenceTracker::new(0.05, std::time::Duration::from_millis(300));
+
+        // Leading silence never stops the recording
+        for _ in 0..10 {
+            assert!(!tracker.update(0.0, step));
+        }
+
+        // Speech, a pause shorter than the timeout, more speech
+        assert!(!tracker.update(0.2, step));
+        assert!(!tracker.update(0.01, step));
+        assert!(!tracker.update(0.01, step));
+        assert!(!tracker.update(0.3, step));
+
+        // Then silence until the timeout
+        assert!(!tracker.update(0.01, step));
+        assert!(!tracker.update(0.01, step));
+        assert!(tracker.update(0.01, step));
+    }
+
+    #[test]
+    fn trims_silence_around_a_tone() {
+        let tone: Vec<f32> = (0..16000)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin() * 0.5)
+            .collect();
+        let samples = [vec![0.0; 16000], tone, vec![0.0; 16000]].concat();
+
+        // The tone plus TRIM_PADDING either side
+        assert_eq!(speech_bounds(&samples, 0.05), 12800..35200);
+        assert_eq!(trim_silence(&samples, 0.05).len(), 22400);
+
+        // Nothing loud enough: nothing is cut
+        assert_eq!(speech_bounds(&samples, 0.9), 0..samples.len());
+    }
+}
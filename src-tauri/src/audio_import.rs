@@ -1,16 +1,48 @@
-use std::fs::File;
+use serde::Serialize;
+use std::fs::{self, File};
 use std::path::Path;
 use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::{Hint, ProbeResult};
+use tauri::{Emitter, Window};
 
-use crate::audio_recorder::resample;
+use crate::audio_analysis::{declip, detect_clipping, speech_bounds, trim_silence, ClipStats};
+use crate::audio_recorder::{resample, StreamingResampler};
 
-/// Convert any supported audio file to 16kHz mono f32 samples for Whisper
-pub fn convert_to_whisper_format(path: &Path) -> Result<Vec<f32>, String> {
+/// Optional processing applied while importing
+#[derive(Clone, Default)]
+pub struct ImportOptions {
+    /// Reconstruct clipped (flat-topped) regions before resampling
+    pub declip: bool,
+    /// Cut leading and trailing silence quieter than this RMS level
+    pub trim_silence: Option<f32>,
+    /// Audio track to decode (see list_audio_tracks); None uses the file's default track
+    pub track_id: Option<u32>,
+}
+
+/// Whisper-ready samples plus what the import found along the way
+pub struct ImportedAudio {
+    pub samples: Vec<f32>,
+    pub clip_stats: ClipStats,
+}
+
+/// Container details and tags of an audio file, read without decoding it
+#[derive(Serialize, Default)]
+pub struct AudioMeta {
+    /// None when the container doesn't record a frame count (common for MP3)
+    pub duration_secs: Option<f32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub date: Option<String>,
+}
+
+/// Open and probe an audio file, using its extension as a format hint
+fn probe_file(path: &Path) -> Result<ProbeResult, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
@@ -22,27 +54,372 @@ pub fn convert_to_whisper_format(path: &Path) -> Result<Vec<f32>, String> {
 
     let format_opts = FormatOptions::default();
     let metadata_opts = MetadataOptions::default();
-    let decoder_opts = DecoderOptions::default();
 
-    let probed = symphonia::default::get_probe()
+    symphonia::default::get_probe()
         .format(&hint, mss, &format_opts, &metadata_opts)
-        .map_err(|e| format!("Unsupported audio format: {}", e))?;
+        .map_err(|e| format!("Unsupported audio format: {}", e))
+}
 
-    let mut format = probed.format;
+/// Fill any title/artist/date not already set from a metadata revision's tags
+fn read_tags(revision: &MetadataRevision, meta: &mut AudioMeta) {
+    for tag in revision.tags() {
+        let slot = match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => &mut meta.title,
+            Some(StandardTagKey::Artist) => &mut meta.artist,
+            Some(
+                StandardTagKey::Date | StandardTagKey::RecordingDate | StandardTagKey::ReleaseDate,
+            ) => &mut meta.date,
+            _ => continue,
+        };
 
-    let track = format
+        let value = tag.value.to_string();
+        if slot.is_none() && !value.trim().is_empty() {
+            *slot = Some(value.trim().to_string());
+        }
+    }
+}
+
+/// Duration, format and tags of an audio file. Files without tags give None fields.
+#[tauri::command]
+pub fn probe_audio_metadata(path: String) -> Result<AudioMeta, String> {
+    let path = Path::new(&path);
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let mut probed = probe_file(path)?;
+
+    let track = probed
+        .format
         .default_track()
         .ok_or("No audio track found in file")?;
+    let params = &track.codec_params;
+
+    let mut meta = AudioMeta {
+        duration_secs: match (params.n_frames, params.sample_rate) {
+            (Some(frames), Some(rate)) if rate > 0 => Some(frames as f32 / rate as f32),
+            _ => None,
+        },
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|c| c.count()),
+        ..Default::default()
+    };
+
+    // Tags found inside the container take precedence over ones read while probing
+    // (e.g. an ID3v2 block in front of an MP3 stream)
+    if let Some(revision) = probed.format.metadata().current() {
+        read_tags(revision, &mut meta);
+    }
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            read_tags(revision, &mut meta);
+        }
+    }
+
+    Ok(meta)
+}
+
+/// One track of a (possibly multi-stream) audio file
+#[derive(Serialize)]
+pub struct TrackInfo {
+    pub id: u32,
+    /// Short codec name, e.g. "aac" or "opus"
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    pub language: Option<String>,
+}
+
+/// Short name of a track's codec, empty when symphonia doesn't know it
+fn codec_name(params: &symphonia::core::codecs::CodecParameters) -> String {
+    symphonia::default::get_codecs()
+        .get_codec(params.codec)
+        .map(|codec| codec.short_name.to_string())
+        .unwrap_or_default()
+}
+
+/// The audio tracks in a file, for choosing one to import
+#[tauri::command]
+pub fn list_audio_tracks(path: String) -> Result<Vec<TrackInfo>, String> {
+    let path = Path::new(&path);
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let format = probe_file(path)?.format;
+
+    Ok(format
+        .tracks()
+        .iter()
+        .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .map(|track| TrackInfo {
+            id: track.id,
+            codec: codec_name(&track.codec_params),
+            sample_rate: track.codec_params.sample_rate,
+            channels: track.codec_params.channels.map(|c| c.count()),
+            language: track.language.clone(),
+        })
+        .collect())
+}
+
+/// Whether a file can be imported, found by decoding only its first packet
+#[derive(Serialize, Default)]
+pub struct AudioValidity {
+    pub decodable: bool,
+    /// Short codec name, e.g. "mp3" or "pcm_s16le"; empty when the file couldn't be probed
+    pub format: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    /// Why the file isn't decodable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Check a file decodes before committing to a full import. Unsupported or corrupt
+/// files give `decodable: false` with a reason rather than an error.
+#[tauri::command]
+pub fn validate_audio_file(path: String) -> Result<AudioValidity, String> {
+    let path = Path::new(&path);
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let invalid = |reason: String| AudioValidity {
+        reason: Some(reason),
+        ..Default::default()
+    };
+
+    let mut format = match probe_file(path) {
+        Ok(probed) => probed.format,
+        Err(e) => return Ok(invalid(e)),
+    };
+
+    let Some(track) = format.default_track() else {
+        return Ok(invalid("No audio track found in file".to_string()));
+    };
+
+    let track_id = track.id;
+    let mut validity = AudioValidity {
+        format: codec_name(&track.codec_params),
+        sample_rate: track.codec_params.sample_rate,
+        channels: track.codec_params.channels.map(|c| c.count()),
+        ..Default::default()
+    };
+
+    let mut decoder = match symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+    {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            validity.reason = Some(format!("Failed to create decoder: {}", e));
+            return Ok(validity);
+        }
+    };
+
+    // Decode the first packet of the track and nothing more
+    let result = loop {
+        match format.next_packet() {
+            Ok(packet) if packet.track_id() != track_id => continue,
+            Ok(packet) => {
+                break decoder
+                    .decode(&packet)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => break Err(format!("No audio data found in file: {}", e)),
+        }
+    };
+
+    match result {
+        Ok(()) => validity.decodable = true,
+        Err(e) => validity.reason = Some(e),
+    }
+
+    Ok(validity)
+}
+
+/// Convert any supported audio file to 16kHz mono f32 samples for Whisper
+pub fn convert_to_whisper_format(path: &Path) -> Result<Vec<f32>, String> {
+    decode_resampled(path, None, |_| {})
+}
+
+/// Convert to Whisper format, emitting `import-progress` events (0-100) to window while decoding
+pub fn convert_to_whisper_format_with_progress(
+    path: &Path,
+    window: &Window,
+) -> Result<Vec<f32>, String> {
+    decode_resampled(path, None, |percent| {
+        let _ = window.emit("import-progress", percent);
+    })
+}
+
+/// Convert to Whisper format, applying the given import options. Unlike
+/// convert_to_whisper_format this holds the whole source in memory, since
+/// clipping is measured (and repaired) across the full buffer.
+pub fn convert_with_options(path: &Path, options: &ImportOptions) -> Result<ImportedAudio, String> {
+    convert_with_progress(path, options, |_| {})
+}
+
+/// Convert to Whisper format with import options, reporting decode progress to on_progress
+pub fn convert_with_progress<P>(
+    path: &Path,
+    options: &ImportOptions,
+    on_progress: P,
+) -> Result<ImportedAudio, String>
+where
+    P: FnMut(u8),
+{
+    let (mut all_samples, source_sample_rate) =
+        decode_mono_with_progress(path, options.track_id, on_progress)?;
+
+    // Clipping is measured at the source rate, before resampling smooths the flat tops
+    let clip_stats = if options.declip {
+        declip(&mut all_samples)
+    } else {
+        detect_clipping(&all_samples)
+    };
+
+    // Resample to 16kHz if needed
+    let mut resampled = if source_sample_rate != 16000 {
+        resample(&all_samples, source_sample_rate, 16000)
+    } else {
+        all_samples
+    };
+
+    if let Some(threshold) = options.trim_silence {
+        resampled = trim_silence(&resampled, threshold).to_vec();
+    }
+
+    Ok(ImportedAudio {
+        samples: resampled,
+        clip_stats,
+    })
+}
+
+/// Whisper-ready samples for transcription, with import options applied. Decodes
+/// through the streaming resampler so long files never sit in memory at their
+/// source rate; only declipping, which needs the full source, falls back to
+/// convert_with_progress.
+pub fn import_samples<P>(
+    path: &Path,
+    options: &ImportOptions,
+    on_progress: P,
+) -> Result<Vec<f32>, String>
+where
+    P: FnMut(u8),
+{
+    if options.declip {
+        return Ok(convert_with_progress(path, options, on_progress)?.samples);
+    }
+
+    let mut samples = decode_resampled(path, options.track_id, on_progress)?;
+    if let Some(threshold) = options.trim_silence {
+        // Trimmed in place rather than copied, as the buffer may be long
+        let bounds = speech_bounds(&samples, threshold);
+        samples.truncate(bounds.end);
+        samples.drain(..bounds.start);
+    }
+
+    Ok(samples)
+}
+
+/// Decode a file to mono f32 samples at its native sample rate
+pub fn decode_mono(path: &Path) -> Result<(Vec<f32>, u32), String> {
+    decode_mono_with_progress(path, None, |_| {})
+}
+
+/// Like decode_mono for the given track (None for the default one), calling
+/// on_progress with the percentage decoded each time it goes up by at least 1%
+pub fn decode_mono_with_progress<P>(
+    path: &Path,
+    track_id: Option<u32>,
+    on_progress: P,
+) -> Result<(Vec<f32>, u32), String>
+where
+    P: FnMut(u8),
+{
+    let mut all_samples = Vec::new();
+    let source_sample_rate = decode_chunks(path, track_id, on_progress, |_, chunk| {
+        all_samples.extend_from_slice(chunk)
+    })?;
+    Ok((all_samples, source_sample_rate))
+}
+
+/// Decode a file straight to 16kHz mono, resampling packet by packet so the
+/// full-rate source is never held in memory alongside the output
+fn decode_resampled<P>(
+    path: &Path,
+    track_id: Option<u32>,
+    on_progress: P,
+) -> Result<Vec<f32>, String>
+where
+    P: FnMut(u8),
+{
+    let mut output = Vec::new();
+    let mut resampler: Option<StreamingResampler> = None;
+
+    decode_chunks(path, track_id, on_progress, |rate, chunk| {
+        if rate == 16000 {
+            output.extend_from_slice(chunk);
+        } else {
+            resampler
+                .get_or_insert_with(|| StreamingResampler::new(rate, 16000))
+                .process(chunk, &mut output);
+        }
+    })?;
+
+    if let Some(resampler) = resampler {
+        resampler.finish(&mut output);
+    }
+
+    Ok(output)
+}
+
+/// Decode one track of a file (the default one when track_id is None) packet by
+/// packet, handing on_chunk the source rate and each packet's samples mixed down
+/// to mono. on_progress gets the percentage decoded each time it goes up by at
+/// least 1%, from the track's frame count or, when that is unknown, from bytes
+/// read against the file size. Returns the source sample rate.
+fn decode_chunks<P, C>(
+    path: &Path,
+    track_id: Option<u32>,
+    mut on_progress: P,
+    mut on_chunk: C,
+) -> Result<u32, String>
+where
+    P: FnMut(u8),
+    C: FnMut(u32, &[f32]),
+{
+    let file_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let decoder_opts = DecoderOptions::default();
+
+    let mut format = probe_file(path)?.format;
+
+    let track = match track_id {
+        Some(id) => format
+            .tracks()
+            .iter()
+            .find(|track| track.id == id)
+            .ok_or_else(|| format!("No track with id {} in file", id))?,
+        None => format
+            .default_track()
+            .ok_or("No audio track found in file")?,
+    };
 
     let track_id = track.id;
     let source_sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
     let source_channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(2);
+    let total_frames = track.codec_params.n_frames.filter(|&n| n > 0);
 
     let mut decoder = symphonia::default::get_codecs()
         .make(&track.codec_params, &decoder_opts)
         .map_err(|e| format!("Failed to create decoder: {}", e))?;
 
-    let mut all_samples: Vec<f32> = Vec::new();
+    let mut sample_count = 0;
+    let mut decoded_frames: u64 = 0;
+    let mut bytes_read: u64 = 0;
+    let mut last_percent: u8 = 0;
+    let mut mono: Vec<f32> = Vec::new();
 
     // Decode all packets
     loop {
@@ -60,6 +437,8 @@ pub fn convert_to_whisper_format(path: &Path) -> Result<Vec<f32>, String> {
             Err(_) => break,
         };
 
+        bytes_read += packet.buf().len() as u64;
+
         if packet.track_id() != track_id {
             continue;
         }
@@ -69,6 +448,19 @@ pub fn convert_to_whisper_format(path: &Path) -> Result<Vec<f32>, String> {
             Err(_) => continue,
         };
 
+        decoded_frames += decoded.frames() as u64;
+        let fraction = match total_frames {
+            Some(total) => decoded_frames as f64 / total as f64,
+            None if file_size > 0 => bytes_read as f64 / file_size as f64,
+            None => 0.0,
+        };
+        // Held below 100 until decoding actually finishes, in case the estimate is off
+        let percent = (fraction * 100.0).min(99.0) as u8;
+        if percent > last_percent {
+            last_percent = percent;
+            on_progress(percent);
+        }
+
         let spec = *decoded.spec();
         let num_channels = spec.channels.count();
 
@@ -79,30 +471,262 @@ pub fn convert_to_whisper_format(path: &Path) -> Result<Vec<f32>, String> {
 
         // Convert to mono if needed
         if num_channels > 1 {
-            for chunk in samples.chunks(num_channels) {
-                let sum: f32 = chunk.iter().sum();
-                all_samples.push(sum / num_channels as f32);
-            }
+            mono.clear();
+            mono.extend(
+                samples
+                    .chunks(num_channels)
+                    .map(|chunk| chunk.iter().sum::<f32>() / num_channels as f32),
+            );
+            on_chunk(source_sample_rate, &mono);
+            sample_count += mono.len();
         } else {
-            all_samples.extend_from_slice(samples);
+            on_chunk(source_sample_rate, samples);
+            sample_count += samples.len();
         }
     }
 
-    if all_samples.is_empty() {
+    if sample_count == 0 {
         return Err("No audio data found in file".to_string());
     }
 
-    // Resample to 16kHz if needed
-    let resampled = if source_sample_rate != 16000 {
-        resample(&all_samples, source_sample_rate, 16000)
-    } else {
-        all_samples
-    };
-
-    Ok(resampled)
+    on_progress(100);
+    Ok(source_sample_rate)
 }
 
 /// Get duration of audio in seconds
 pub fn get_audio_duration(samples: &[f32], sample_rate: u32) -> f32 {
     samples.len() as f32 / sample_rate as f32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_recorder::write_wav;
+
+    fn sine(freq: f32, rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    fn wav_fixture(dir: &Path, name: &str, rate: u32, secs: f32) -> String {
+        let path = dir.join(name);
+        write_wav(
+            &sine(440.0, rate, (rate as f32 * secs) as usize),
+            rate,
+            &path,
+        )
+        .unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// An ID3v2.3 text frame
+    fn id3_frame(id: &str, text: &str) -> Vec<u8> {
+        let mut frame = id.as_bytes().to_vec();
+        frame.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0]); // flags, then ISO-8859-1 encoding
+        frame.extend_from_slice(text.as_bytes());
+        frame
+    }
+
+    /// An MP3 of silent 128 kbps mono frames behind an ID3v2 tag
+    fn tagged_mp3(path: &Path, title: &str, artist: &str) {
+        let frames = [id3_frame("TIT2", title), id3_frame("TPE1", artist)].concat();
+        let size = frames.len() as u32;
+        // Tag sizes are syncsafe: 7 bits per byte
+        let syncsafe = [21, 14, 7, 0].map(|shift| ((size >> shift) & 0x7f) as u8);
+
+        let mut bytes = b"ID3\x03\x00\x00".to_vec();
+        bytes.extend_from_slice(&syncsafe);
+        bytes.extend_from_slice(&frames);
+
+        // MPEG-1 layer III, 128 kbps, 44.1 kHz, mono: 417 bytes a frame. Zeroed side
+        // info and main data decode to silence.
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+        for _ in 0..20 {
+            bytes.extend_from_slice(&frame);
+        }
+
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn metadata_of_an_untagged_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = wav_fixture(dir.path(), "plain.wav", 16000, 1.5);
+
+        let meta = probe_audio_metadata(path).unwrap();
+
+        assert_eq!(meta.duration_secs, Some(1.5));
+        assert_eq!(meta.sample_rate, Some(16000));
+        assert_eq!(meta.channels, Some(1));
+        assert_eq!(meta.title, None);
+        assert_eq!(meta.artist, None);
+        assert_eq!(meta.date, None);
+    }
+
+    #[test]
+    fn metadata_of_a_tagged_mp3() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.mp3");
+        tagged_mp3(&path, "Morning notes", "Sam");
+
+        let meta = probe_audio_metadata(path.to_string_lossy().into_owned()).unwrap();
+
+        assert_eq!(meta.title.as_deref(), Some("Morning notes"));
+        assert_eq!(meta.artist.as_deref(), Some("Sam"));
+        assert_eq!(meta.date, None);
+        assert_eq!(meta.sample_rate, Some(44100));
+    }
+
+    #[test]
+    fn streaming_import_matches_the_buffered_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = wav_fixture(dir.path(), "long.wav", 44100, 30.0);
+        let path = Path::new(&path);
+
+        let streamed = import_samples(path, &ImportOptions::default(), |_| {}).unwrap();
+        let buffered = convert_with_options(path, &ImportOptions::default())
+            .unwrap()
+            .samples;
+
+        assert!(streamed.len().abs_diff(buffered.len()) <= 1);
+        assert!(streamed.len().abs_diff(30 * 16000) <= 1);
+        assert!(streamed
+            .iter()
+            .zip(&buffered)
+            .all(|(a, b)| (a - b).abs() < 1e-4));
+    }
+
+    #[test]
+    fn validates_a_wav_and_rejects_renamed_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let wav = wav_fixture(dir.path(), "ok.wav", 16000, 0.5);
+
+        let valid = validate_audio_file(wav).unwrap();
+        assert!(valid.decodable);
+        assert_eq!(valid.sample_rate, Some(16000));
+        assert_eq!(valid.channels, Some(1));
+        assert_eq!(valid.reason, None);
+
+        let fake = dir.path().join("notes.mp3");
+        fs::write(&fake, "Just some text, not audio at all.\n".repeat(50)).unwrap();
+
+        let invalid = validate_audio_file(fake.to_string_lossy().into_owned()).unwrap();
+        assert!(!invalid.decodable);
+        assert!(invalid.reason.is_some());
+    }
+
+    /// An EBML element with an 8-byte size
+    fn ebml(id: u32, body: &[u8]) -> Vec<u8> {
+        let id_bytes = id.to_be_bytes();
+        let skip = id_bytes.iter().position(|&b| b != 0).unwrap_or(3);
+        let mut element = id_bytes[skip..].to_vec();
+        element.push(0x01);
+        element.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        element.extend_from_slice(body);
+        element
+    }
+
+    fn ebml_uint(id: u32, value: u64) -> Vec<u8> {
+        ebml(id, &value.to_be_bytes())
+    }
+
+    /// A Matroska file with two mono 16-bit PCM tracks at 16kHz
+    fn two_track_mkv(path: &Path) {
+        let header = ebml(
+            0x1A45DFA3,
+            &[
+                ebml_uint(0x4286, 1),
+                ebml_uint(0x42F7, 1),
+                ebml_uint(0x42F2, 4),
+                ebml_uint(0x42F3, 8),
+                ebml(0x4282, b"matroska"),
+                ebml_uint(0x4287, 4),
+                ebml_uint(0x4285, 2),
+            ]
+            .concat(),
+        );
+
+        let track = |number: u64, language: &str| {
+            let audio = [
+                ebml(0xB5, &16000f64.to_be_bytes()),
+                ebml_uint(0x9F, 1),
+                ebml_uint(0x6264, 16),
+            ]
+            .concat();
+            ebml(
+                0xAE,
+                &[
+                    ebml_uint(0xD7, number),
+                    ebml_uint(0x73C5, number),
+                    ebml_uint(0x83, 2),
+                    ebml(0x86, b"A_PCM/INT/LIT"),
+                    ebml(0x22B59C, language.as_bytes()),
+                    ebml(0xE1, &audio),
+                ]
+                .concat(),
+            )
+        };
+
+        // A keyframe holding 0.1s of each track
+        let block = |number: u8| {
+            let mut body = vec![0x80 | number, 0, 0, 0x80];
+            body.extend(
+                sine(440.0, 16000, 1600)
+                    .iter()
+                    .flat_map(|&s| ((s * i16::MAX as f32) as i16).to_le_bytes()),
+            );
+            ebml(0xA3, &body)
+        };
+
+        let segment = ebml(
+            0x18538067,
+            &[
+                ebml(0x1549A966, &ebml_uint(0x2AD7B1, 1_000_000)),
+                ebml(0x1654AE6B, &[track(1, "eng"), track(2, "fra")].concat()),
+                ebml(
+                    0x1F43B675,
+                    &[ebml_uint(0xE7, 0), block(1), block(2)].concat(),
+                ),
+            ]
+            .concat(),
+        );
+
+        fs::write(path, [header, segment].concat()).unwrap();
+    }
+
+    #[test]
+    fn lists_both_tracks_of_a_two_track_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("two.mkv");
+        two_track_mkv(&path);
+
+        let tracks = list_audio_tracks(path.to_string_lossy().into_owned()).unwrap();
+
+        assert_eq!(tracks.len(), 2);
+        assert_ne!(tracks[0].id, tracks[1].id);
+        for track in &tracks {
+            assert_eq!(track.sample_rate, Some(16000));
+            assert_eq!(track.channels, Some(1));
+            assert!(!track.codec.is_empty());
+        }
+    }
+
+    #[test]
+    fn import_progress_climbs_to_100() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = wav_fixture(dir.path(), "lecture.wav", 16000, 20.0);
+
+        let mut reported = Vec::new();
+        decode_mono_with_progress(Path::new(&path), None, |percent| reported.push(percent))
+            .unwrap();
+
+        assert!(reported.len() > 2);
+        assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+        // Held below 100 until decoding is done, then always finished with 100
+        assert_eq!(reported.pop(), Some(100));
+        assert!(reported.iter().all(|&percent| percent <= 99));
+    }
+}
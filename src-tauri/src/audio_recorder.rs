@@ -1,24 +1,60 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::sync::mpsc;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    Arc, Mutex,
+};
 use std::thread;
+use tauri::{Emitter, Window};
+
+use crate::audio_analysis::{rms, SilenceTracker, DEFAULT_SILENCE_RMS};
 
 /// Thread-safe audio samples storage
 pub struct SharedSamples {
     samples: Mutex<Vec<f32>>,
+    // Rate of the buffered samples: the device rate while recording, 16kHz once resampled.
+    // Only changed while holding the samples lock so readers see a consistent pair.
+    sample_rate: AtomicU32,
     is_recording: AtomicBool,
+    // Incremented on every start so timers can tell recordings apart
+    session: AtomicU64,
+    // Set by the stream's error callback, e.g. when the device is unplugged
+    stream_errored: AtomicBool,
+    stream_error: Mutex<Option<String>>,
 }
 
 impl SharedSamples {
     pub fn new() -> Arc<Self> {
         Arc::new(Self {
             samples: Mutex::new(Vec::new()),
+            sample_rate: AtomicU32::new(16000),
             is_recording: AtomicBool::new(false),
+            session: AtomicU64::new(0),
+            stream_errored: AtomicBool::new(false),
+            stream_error: Mutex::new(None),
         })
     }
 
-    pub fn start_recording(&self) {
+    /// Atomically claim the recorder. Returns false if a recording is already in progress.
+    pub fn start_recording(&self) -> bool {
+        if self
+            .is_recording
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+
         self.samples.lock().unwrap().clear();
-        self.is_recording.store(true, Ordering::SeqCst);
+        *self.stream_error.lock().unwrap() = None;
+        self.stream_errored.store(false, Ordering::SeqCst);
+        self.session.fetch_add(1, Ordering::SeqCst);
+        true
+    }
+
+    /// Identifier of the current (or most recent) recording
+    pub fn session(&self) -> u64 {
+        self.session.load(Ordering::SeqCst)
     }
 
     pub fn stop_recording(&self) {
@@ -38,146 +74,739 @@ impl SharedSamples {
     pub fn get_samples(&self) -> Vec<f32> {
         self.samples.lock().unwrap().clone()
     }
+
+    /// Record that the input stream failed; the recording thread stops at its next poll
+    pub fn set_stream_error(&self, message: String) {
+        *self.stream_error.lock().unwrap() = Some(message);
+        self.stream_errored.store(true, Ordering::SeqCst);
+    }
+
+    pub fn stream_errored(&self) -> bool {
+        self.stream_errored.load(Ordering::SeqCst)
+    }
+
+    /// Stop the recording if its input stream has failed, returning whether it did
+    pub fn stop_if_stream_errored(&self) -> bool {
+        if !self.stream_errored() {
+            return false;
+        }
+        self.stop_recording();
+        true
+    }
+
+    /// Message of the last stream error, if the current recording hit one
+    pub fn stream_error(&self) -> Option<String> {
+        self.stream_error.lock().unwrap().clone()
+    }
+
+    /// Number of samples buffered so far
+    pub fn sample_count(&self) -> usize {
+        self.samples.lock().unwrap().len()
+    }
+
+    /// Rate of the buffered samples: the device rate while recording, 16kHz after stop
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::SeqCst)
+    }
+
+    /// Seconds of audio buffered at sample_rate. While recording, pass the device
+    /// rate from sample_rate(), since resampling only happens at stop.
+    pub fn elapsed_secs(&self, sample_rate: u32) -> f32 {
+        if sample_rate == 0 {
+            return 0.0;
+        }
+        self.sample_count() as f32 / sample_rate as f32
+    }
+
+    fn set_sample_rate(&self, rate: u32) {
+        let _samples = self.samples.lock().unwrap();
+        self.sample_rate.store(rate, Ordering::SeqCst);
+    }
+
+    /// Copy the samples from offset onwards together with the rate they are at
+    pub fn snapshot_from(&self, offset: usize) -> (Vec<f32>, u32) {
+        let samples = self.samples.lock().unwrap();
+        let rate = self.sample_rate.load(Ordering::SeqCst);
+        let start = offset.min(samples.len());
+        (samples[start..].to_vec(), rate)
+    }
 }
 
-/// Start recording audio in a background thread
-/// Returns a handle that stops recording when dropped
-pub fn start_recording_thread(shared: Arc<SharedSamples>) -> Result<thread::JoinHandle<()>, String> {
-    shared.start_recording();
+fn is_supported_format(format: cpal::SampleFormat) -> bool {
+    matches!(
+        format,
+        cpal::SampleFormat::F32 | cpal::SampleFormat::I16 | cpal::SampleFormat::U16
+    )
+}
 
-    let handle = thread::spawn(move || {
-        let host = cpal::default_host();
+/// Map a signed 16-bit sample onto -1.0..1.0
+fn i16_to_f32(sample: i16) -> f32 {
+    sample as f32 / 32768.0
+}
 
-        let device = match host.default_input_device() {
-            Some(d) => d,
-            None => {
-                eprintln!("No input device available");
-                return;
-            }
-        };
+/// Map an unsigned 16-bit sample (silence at 32768) onto -1.0..1.0
+fn u16_to_f32(sample: u16) -> f32 {
+    (sample as f32 - 32768.0) / 32768.0
+}
 
-        let supported_configs = match device.supported_input_configs() {
-            Ok(c) => c,
-            Err(e) => {
-                eprintln!("Failed to get supported configs: {}", e);
-                return;
-            }
-        };
+/// Build an input stream of sample type T, handing on_samples each buffer as f32
+fn build_input_stream<T, F, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut on_samples: F,
+    on_error: E,
+    convert: fn(T) -> f32,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample,
+    F: FnMut(&[f32]) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let samples: Vec<f32> = data.iter().map(|&s| convert(s)).collect();
+            on_samples(&samples);
+        },
+        on_error,
+        None,
+    )
+}
 
-        // Find a suitable config
-        let config = match supported_configs
-            .filter(|c| c.channels() == 1 || c.channels() == 2)
-            .min_by_key(|c| {
-                let min = c.min_sample_rate().0;
-                let max = c.max_sample_rate().0;
-                if 16000 >= min && 16000 <= max {
-                    0
-                } else if 16000 < min {
-                    min - 16000
-                } else {
-                    16000 - max
-                }
-            }) {
-            Some(c) => c,
-            None => {
-                eprintln!("No suitable audio config found");
-                return;
+/// Optional behaviour for a recording
+#[derive(Default)]
+pub struct RecordingOptions {
+    /// Window that receives `recording-level` events (0.0-1.0) while recording
+    pub window: Option<Window>,
+    /// Name of the input device to record from; None uses the system default
+    pub device_name: Option<String>,
+    /// Stop automatically after this many seconds of audio, emitting `recording-countdown`
+    /// (seconds left) each second; None records until stopped
+    pub max_duration_secs: Option<u32>,
+    /// Level below which input counts as silence (defaults to DEFAULT_SILENCE_RMS)
+    pub silence_rms: Option<f32>,
+    /// Stop after this much silence following speech; None disables silence detection
+    pub silence_timeout_secs: Option<f32>,
+}
+
+/// Number of samples making up secs seconds of audio at sample_rate
+fn samples_for_duration(secs: u32, sample_rate: u32) -> usize {
+    secs as usize * sample_rate as usize
+}
+
+/// Find the named input device, falling back to the default when it isn't connected
+fn select_input_device(host: &cpal::Host, device_name: Option<&str>) -> Option<cpal::Device> {
+    if let Some(name) = device_name {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+
+        match found {
+            Some(device) => return Some(device),
+            None => eprintln!("Input device '{}' not found, using the default", name),
+        }
+    }
+
+    host.default_input_device()
+}
+
+/// Names of the available input devices
+#[tauri::command]
+pub fn list_input_devices() -> Result<Vec<String>, String> {
+    let host = cpal::default_host();
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list input devices: {}", e))?;
+
+    Ok(devices.filter_map(|d| d.name().ok()).collect())
+}
+
+/// Open the input device and start a stream feeding shared. Returns the stream,
+/// which records until dropped, its sample rate, and the level of the latest buffer
+/// (as f32 bits).
+fn open_input_stream(
+    shared: &Arc<SharedSamples>,
+    device_name: Option<&str>,
+) -> Result<(cpal::Stream, u32, Arc<AtomicU32>), String> {
+    let host = cpal::default_host();
+
+    let device = select_input_device(&host, device_name).ok_or("No input device available")?;
+
+    let supported_configs = device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to get supported configs: {}", e))?;
+
+    // Find a suitable config
+    let config = match supported_configs
+        .filter(|c| c.channels() == 1 || c.channels() == 2)
+        .filter(|c| is_supported_format(c.sample_format()))
+        .min_by_key(|c| {
+            let min = c.min_sample_rate().0;
+            let max = c.max_sample_rate().0;
+            if 16000 >= min && 16000 <= max {
+                0
+            } else if 16000 < min {
+                min - 16000
+            } else {
+                16000 - max
             }
-        };
+        }) {
+        Some(c) => c,
+        None => return Err("No suitable audio config found".to_string()),
+    };
+
+    let sample_rate = if config.min_sample_rate().0 <= 16000 && config.max_sample_rate().0 >= 16000
+    {
+        cpal::SampleRate(16000)
+    } else {
+        config.min_sample_rate()
+    };
+
+    let config = config.with_sample_rate(sample_rate);
+    let channels = config.channels() as usize;
+    let source_sample_rate = config.sample_rate().0;
+    shared.set_sample_rate(source_sample_rate);
+
+    let shared_clone = shared.clone();
+    // Level of the latest buffer as f32 bits, written by the audio callback
+    let level = Arc::new(AtomicU32::new(0));
+    let callback_level = level.clone();
+
+    let sample_format = config.sample_format();
+    let stream_config: cpal::StreamConfig = config.into();
 
-        let sample_rate = if config.min_sample_rate().0 <= 16000 && config.max_sample_rate().0 >= 16000 {
-            cpal::SampleRate(16000)
+    let on_samples = move |data: &[f32]| {
+        if !shared_clone.is_recording() {
+            return;
+        }
+
+        // Convert to mono if stereo
+        let mono_samples: Vec<f32> = if channels == 2 {
+            data.chunks(2)
+                .filter_map(|chunk| {
+                    if chunk.len() == 2 {
+                        Some((chunk[0] + chunk[1]) / 2.0)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
         } else {
-            config.min_sample_rate()
+            data.to_vec()
         };
 
-        let config = config.with_sample_rate(sample_rate);
-        let channels = config.channels() as usize;
-        let source_sample_rate = config.sample_rate().0;
+        callback_level.store(rms(&mono_samples).min(1.0).to_bits(), Ordering::Relaxed);
+        shared_clone.add_samples(&mono_samples);
+    };
+
+    let error_shared = shared.clone();
+    let on_error = move |err: cpal::StreamError| {
+        eprintln!("Audio stream error: {}", err);
+        error_shared.set_stream_error(err.to_string());
+    };
+
+    // Integer streams are normalized to f32 before the shared mono/level path
+    let stream = match sample_format {
+        cpal::SampleFormat::I16 => {
+            build_input_stream(&device, &stream_config, on_samples, on_error, i16_to_f32)
+        }
+        cpal::SampleFormat::U16 => {
+            build_input_stream(&device, &stream_config, on_samples, on_error, u16_to_f32)
+        }
+        _ => build_input_stream(&device, &stream_config, on_samples, on_error, |s: f32| s),
+    };
+
+    let stream = stream.map_err(|e| format!("Failed to build input stream: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start stream: {}", e))?;
+
+    Ok((stream, source_sample_rate, level))
+}
+
+/// Start recording audio in a background thread
+/// Returns a handle that stops recording when dropped
+pub fn start_recording_thread(
+    shared: Arc<SharedSamples>,
+    options: RecordingOptions,
+) -> Result<thread::JoinHandle<()>, String> {
+    if !shared.start_recording() {
+        return Err("Already recording".to_string());
+    }
 
-        let shared_clone = shared.clone();
+    let (started_tx, started_rx) = mpsc::sync_channel(1);
+    let thread_shared = shared.clone();
 
-        let stream = match device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if !shared_clone.is_recording() {
+    let handle = thread::spawn(move || {
+        let shared = thread_shared;
+        let (stream, source_sample_rate, level) =
+            match open_input_stream(&shared, options.device_name.as_deref()) {
+                Ok(opened) => {
+                    let _ = started_tx.send(Ok(()));
+                    opened
+                }
+                Err(e) => {
+                    shared.stop_recording();
+                    let _ = started_tx.send(Err(e));
                     return;
                 }
+            };
 
-                // Convert to mono if stereo
-                let mono_samples: Vec<f32> = if channels == 2 {
-                    data.chunks(2)
-                        .filter_map(|chunk| {
-                            if chunk.len() == 2 {
-                                Some((chunk[0] + chunk[1]) / 2.0)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
-                } else {
-                    data.to_vec()
-                };
-
-                shared_clone.add_samples(&mono_samples);
-            },
-            |err| {
-                eprintln!("Audio stream error: {}", err);
-            },
-            None,
-        ) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Failed to build input stream: {}", e);
-                return;
+        let sample_limit = options
+            .max_duration_secs
+            .map(|secs| samples_for_duration(secs, source_sample_rate));
+        let mut silence = options.silence_timeout_secs.map(|secs| {
+            SilenceTracker::new(
+                options.silence_rms.unwrap_or(DEFAULT_SILENCE_RMS),
+                std::time::Duration::try_from_secs_f32(secs.max(0.0))
+                    .unwrap_or(std::time::Duration::MAX),
+            )
+        });
+        let poll_interval = std::time::Duration::from_millis(50);
+        let mut last_countdown: Option<u32> = None;
+
+        // Keep the stream alive while recording. Levels are emitted from here rather
+        // than the audio callback so the callback never waits on the bridge (~20 Hz).
+        while shared.is_recording() {
+            thread::sleep(poll_interval);
+
+            // The device went away; keep what was captured before it did
+            if shared.stop_if_stream_errored() {
+                if let Some(window) = &options.window {
+                    let _ = window.emit("recording-error", shared.stream_error());
+                }
+                break;
             }
-        };
 
-        if let Err(e) = stream.play() {
-            eprintln!("Failed to start stream: {}", e);
-            return;
-        }
+            if let Some(limit) = sample_limit {
+                let recorded = shared.samples.lock().unwrap().len();
+                if recorded >= limit {
+                    shared.stop_recording();
+                    if let Some(window) = &options.window {
+                        let _ = window.emit("recording-auto-stopped", options.max_duration_secs);
+                    }
+                    break;
+                }
 
-        // Keep the stream alive while recording
-        while shared.is_recording() {
-            thread::sleep(std::time::Duration::from_millis(50));
+                // Whole seconds left, announced each time it ticks down
+                let remaining = (limit - recorded).div_ceil(source_sample_rate as usize) as u32;
+                if last_countdown != Some(remaining) {
+                    last_countdown = Some(remaining);
+                    if let Some(window) = &options.window {
+                        let _ = window.emit("recording-countdown", remaining);
+                    }
+                }
+            }
+
+            let current_level = f32::from_bits(level.load(Ordering::Relaxed));
+            if let Some(window) = &options.window {
+                let _ = window.emit("recording-level", current_level);
+            }
+
+            if let Some(tracker) = silence.as_mut() {
+                if tracker.update(current_level, poll_interval) {
+                    shared.stop_recording();
+                    if let Some(window) = &options.window {
+                        let _ = window.emit("recording-silence-stopped", ());
+                    }
+                    break;
+                }
+            }
         }
 
-        // Stream is dropped here, stopping the recording
+        // Dropping the stream stops the recording
+        drop(stream);
 
         // Resample if needed
         if source_sample_rate != 16000 {
-            let samples = shared.get_samples();
-            let resampled = resample(&samples, source_sample_rate, 16000);
-            *shared.samples.lock().unwrap() = resampled;
+            let mut samples = shared.samples.lock().unwrap();
+            *samples = resample(&samples, source_sample_rate, 16000);
+            shared.sample_rate.store(16000, Ordering::SeqCst);
         }
     });
 
-    Ok(handle)
+    // Wait until the device is open, so a failure reaches the caller rather than
+    // leaving a recording that never captures anything
+    match started_rx.recv() {
+        Ok(Ok(())) => Ok(handle),
+        Ok(Err(e)) => {
+            let _ = handle.join();
+            Err(e)
+        }
+        Err(_) => {
+            // The thread panicked before reporting back
+            shared.stop_recording();
+            let _ = handle.join();
+            Err("Recording thread failed to start".to_string())
+        }
+    }
+}
+
+// Zero crossings of the sinc kernel kept either side of each output sample
+const SINC_ZERO_CROSSINGS: f64 = 8.0;
+// Kernel table entries per input sample; weights in between are interpolated
+const KERNEL_STEPS: usize = 512;
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Blackman window over t in -1..1
+fn blackman(t: f64) -> f64 {
+    let pt = std::f64::consts::PI * t;
+    0.42 + 0.5 * pt.cos() + 0.08 * (2.0 * pt).cos()
+}
+
+/// The windowed-sinc kernel for one rate ratio, tabulated once over its right half
+/// (it's symmetric) so each tap is a lookup instead of a sin and two cos calls
+struct SincKernel {
+    /// Kernel half-width in input samples
+    half_width: f64,
+    table: Vec<f64>,
+}
+
+impl SincKernel {
+    fn new(ratio: f64) -> Self {
+        // Cutoff relative to the input Nyquist, lowered to the output's when downsampling
+        let cutoff = ratio.min(1.0);
+        let half_width = SINC_ZERO_CROSSINGS / cutoff;
+
+        // One entry past the edge so interpolation near it has a right neighbour
+        let len = (half_width * KERNEL_STEPS as f64).ceil() as usize + 2;
+        let table = (0..len)
+            .map(|k| {
+                let x = k as f64 / KERNEL_STEPS as f64;
+                if x >= half_width {
+                    0.0
+                } else {
+                    sinc(cutoff * x) * blackman(x / half_width)
+                }
+            })
+            .collect();
+
+        Self { half_width, table }
+    }
+
+    /// Weight of an input sample x input samples away from the output position
+    fn weight(&self, x: f64) -> f64 {
+        let position = x.abs() * KERNEL_STEPS as f64;
+        let i = position as usize;
+        if i + 1 >= self.table.len() {
+            return 0.0;
+        }
+
+        let frac = position - i as f64;
+        self.table[i] + (self.table[i + 1] - self.table[i]) * frac
+    }
+}
+
+/// Filtered value at input position center, from samples that start at absolute
+/// index offset. Input outside the slice is left out rather than treated as zero.
+fn sinc_at(samples: &[f32], offset: usize, center: f64, kernel: &SincKernel) -> f32 {
+    let first = ((center - kernel.half_width).ceil().max(0.0) as usize).max(offset);
+    let last = (center + kernel.half_width).floor().max(0.0) as usize;
+
+    let (sum, weight_sum) = samples
+        .iter()
+        .enumerate()
+        .take((last + 1).saturating_sub(offset))
+        .skip(first - offset)
+        .fold((0.0f64, 0.0f64), |(sum, weight_sum), (j, &s)| {
+            let weight = kernel.weight((offset + j) as f64 - center);
+            (sum + s as f64 * weight, weight_sum + weight)
+        });
+
+    // Normalizing by the kernel sum keeps unity gain, including at the edges
+    if weight_sum.abs() > 1e-9 {
+        (sum / weight_sum) as f32
+    } else {
+        0.0
+    }
 }
 
-/// Resample audio from one sample rate to another using linear interpolation
+/// Resample audio from one sample rate to another with a windowed-sinc filter.
+/// When downsampling the filter cuts off at the new Nyquist frequency so speech
+/// harmonics above it don't alias back into the band.
 pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate || samples.is_empty() {
+    if from_rate == to_rate || samples.is_empty() || from_rate == 0 || to_rate == 0 {
         return samples.to_vec();
     }
 
-    let ratio = from_rate as f64 / to_rate as f64;
-    let new_len = (samples.len() as f64 / ratio) as usize;
+    let ratio = to_rate as f64 / from_rate as f64;
+    // Rounded rather than truncated so long recordings keep their length
+    let new_len = (samples.len() as f64 * ratio).round() as usize;
+    let kernel = SincKernel::new(ratio);
 
     (0..new_len)
-        .map(|i| {
-            let src_idx = i as f64 * ratio;
-            let idx = src_idx as usize;
-            let frac = (src_idx - idx as f64) as f32;
-
-            if idx + 1 < samples.len() {
-                samples[idx] * (1.0 - frac) + samples[idx + 1] * frac
-            } else if idx < samples.len() {
-                samples[idx]
-            } else {
-                0.0
-            }
-        })
+        .map(|i| sinc_at(samples, 0, i as f64 / ratio, &kernel))
         .collect()
 }
+
+/// The resample filter applied to input arriving in pieces. Only the input still
+/// inside the kernel window is kept, and the output matches resample() on the
+/// whole buffer.
+pub struct StreamingResampler {
+    ratio: f64,
+    kernel: SincKernel,
+    // Input not yet behind every future output's window, starting at pending_start
+    pending: Vec<f32>,
+    pending_start: usize,
+    consumed: usize,
+    produced: usize,
+}
+
+impl StreamingResampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let ratio = to_rate.max(1) as f64 / from_rate.max(1) as f64;
+
+        Self {
+            ratio,
+            kernel: SincKernel::new(ratio),
+            pending: Vec::new(),
+            pending_start: 0,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    /// Feed the next piece of input, appending every output sample it completes
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.pending.extend_from_slice(input);
+        self.consumed += input.len();
+
+        loop {
+            let center = self.produced as f64 / self.ratio;
+            // Wait until the whole window for this output sample has arrived
+            if (center + self.kernel.half_width).floor() as usize >= self.consumed {
+                break;
+            }
+            output.push(sinc_at(
+                &self.pending,
+                self.pending_start,
+                center,
+                &self.kernel,
+            ));
+            self.produced += 1;
+        }
+
+        let keep_from = (self.produced as f64 / self.ratio - self.kernel.half_width)
+            .ceil()
+            .max(0.0) as usize;
+        let drop = keep_from
+            .saturating_sub(self.pending_start)
+            .min(self.pending.len());
+        self.pending.drain(..drop);
+        self.pending_start += drop;
+    }
+
+    /// Append the remaining output once all input has been fed
+    pub fn finish(self, output: &mut Vec<f32>) {
+        let total = (self.consumed as f64 * self.ratio).round() as usize;
+        for i in self.produced..total {
+            let center = i as f64 / self.ratio;
+            output.push(sinc_at(
+                &self.pending,
+                self.pending_start,
+                center,
+                &self.kernel,
+            ));
+        }
+    }
+}
+
+/// Convert a normalized sample to 16-bit PCM, clamping anything out of range
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Write mono samples as a 16-bit PCM WAV file
+pub fn write_wav(samples: &[f32], sample_rate: u32, path: &std::path::Path) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer =
+        hound::WavWriter::create(path, spec).map_err(|e| format!("Failed to create WAV: {}", e))?;
+    for &sample in samples {
+        writer
+            .write_sample(to_i16(sample))
+            .map_err(|e| format!("Failed to write WAV: {}", e))?;
+    }
+
+    // finalize fills in the RIFF and data chunk sizes
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to write WAV: {}", e))
+}
+
+/// Capture a short throwaway recording on its own buffer, for level checks.
+/// Returns the samples at 16kHz.
+pub fn record_for(duration: std::time::Duration) -> Result<Vec<f32>, String> {
+    let shared = SharedSamples::new();
+    let handle = start_recording_thread(shared.clone(), RecordingOptions::default())?;
+
+    thread::sleep(duration);
+    shared.stop_recording();
+    handle.join().map_err(|_| "Recording thread panicked")?;
+
+    Ok(shared.get_samples())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_input_devices_runs() {
+        // Machines without an audio backend (e.g. CI containers) can't list anything
+        match list_input_devices() {
+            Ok(names) => assert!(names.iter().all(|name| !name.is_empty())),
+            Err(e) => eprintln!("Skipping, no audio host: {}", e),
+        }
+    }
+
+    #[test]
+    fn duration_limit_in_samples() {
+        assert_eq!(samples_for_duration(30, 16000), 480_000);
+        assert_eq!(samples_for_duration(1, 44100), 44_100);
+        assert_eq!(samples_for_duration(3600, 48000), 172_800_000);
+        assert_eq!(samples_for_duration(0, 48000), 0);
+    }
+
+    #[test]
+    fn wav_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip.wav");
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 2.0];
+
+        write_wav(&samples, 16000, &path).unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 1);
+        assert_eq!(spec.sample_rate, 16000);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+        assert_eq!(reader.len(), samples.len() as u32);
+
+        let read: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        // Out of range input is clamped rather than wrapping
+        assert_eq!(read, [0, 16383, -16383, i16::MAX, -i16::MAX, i16::MAX]);
+    }
+
+    #[test]
+    fn integer_samples_map_to_unit_range() {
+        assert_eq!(i16_to_f32(i16::MIN), -1.0);
+        assert_eq!(i16_to_f32(0), 0.0);
+        assert!((i16_to_f32(i16::MAX) - 1.0).abs() < 1e-4);
+
+        assert_eq!(u16_to_f32(0), -1.0);
+        assert_eq!(u16_to_f32(32768), 0.0);
+        assert!((u16_to_f32(u16::MAX) - 1.0).abs() < 1e-4);
+    }
+
+    fn sine(freq: f64, rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                (2.0 * std::f64::consts::PI * freq * i as f64 / rate as f64).sin() as f32 * 0.5
+            })
+            .collect()
+    }
+
+    /// Frequency of a tone from its zero crossings
+    fn zero_crossing_freq(samples: &[f32], rate: u32) -> f64 {
+        let crossings = samples
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count();
+        crossings as f64 / 2.0 / (samples.len() as f64 / rate as f64)
+    }
+
+    #[test]
+    fn resampling_keeps_frequency_and_length() {
+        for (from, to) in [(48000, 16000), (44100, 16000), (8000, 16000)] {
+            let input = sine(440.0, from, from as usize);
+            let output = resample(&input, from, to);
+
+            assert!(output.len().abs_diff(to as usize) <= 1);
+            assert!((zero_crossing_freq(&output, to) - 440.0).abs() < 2.0);
+        }
+
+        // Lengths that don't divide evenly are within one sample of the ideal
+        let odd = resample(&sine(440.0, 44100, 12345), 44100, 16000);
+        let ideal = 12345.0 * 16000.0 / 44100.0;
+        assert!((odd.len() as f64 - ideal).abs() <= 1.0);
+    }
+
+    #[test]
+    fn kernel_table_matches_the_direct_kernel() {
+        for ratio in [16000.0 / 48000.0, 16000.0 / 44100.0, 2.0] {
+            let kernel = SincKernel::new(ratio);
+            let cutoff = f64::min(ratio, 1.0);
+
+            for step in 0..1000 {
+                let x = (step as f64 / 1000.0 - 0.5) * 2.0 * kernel.half_width;
+                let direct = sinc(cutoff * x) * blackman(x / kernel.half_width);
+                assert!((kernel.weight(x) - direct).abs() < 1e-4);
+            }
+            assert_eq!(kernel.weight(kernel.half_width + 1.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn streaming_resampler_matches_resample() {
+        let input = sine(440.0, 44100, 44100);
+        let whole = resample(&input, 44100, 16000);
+
+        let mut resampler = StreamingResampler::new(44100, 16000);
+        let mut streamed = Vec::new();
+        for piece in input.chunks(1000) {
+            resampler.process(piece, &mut streamed);
+        }
+        resampler.finish(&mut streamed);
+
+        assert_eq!(streamed, whole);
+    }
+
+    #[test]
+    fn elapsed_secs_from_buffered_samples() {
+        let shared = SharedSamples::new();
+        assert!(shared.start_recording());
+        shared.add_samples(&vec![0.0; 24000]);
+
+        assert_eq!(shared.sample_count(), 24000);
+        assert_eq!(shared.elapsed_secs(48000), 0.5);
+        assert_eq!(shared.elapsed_secs(16000), 1.5);
+        assert_eq!(shared.elapsed_secs(0), 0.0);
+    }
+
+    #[test]
+    fn stream_error_ends_the_recording_loop() {
+        let shared = SharedSamples::new();
+        assert!(shared.start_recording());
+        assert!(!shared.stop_if_stream_errored());
+        assert!(shared.is_recording());
+
+        shared.set_stream_error("device unplugged".to_string());
+        assert!(shared.stream_errored());
+        assert_eq!(shared.stream_error().as_deref(), Some("device unplugged"));
+
+        // What the poll loop checks each time round
+        assert!(shared.stop_if_stream_errored());
+        assert!(!shared.is_recording());
+
+        // The next recording starts clean
+        assert!(shared.start_recording());
+        assert!(!shared.stream_errored());
+        assert_eq!(shared.stream_error(), None);
+    }
+}
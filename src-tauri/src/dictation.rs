@@ -0,0 +1,120 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, Window};
+
+use crate::audio_recorder::{resample, SharedSamples};
+use crate::transcription;
+
+const POLL_INTERVAL_MS: u64 = 250;
+
+/// An offset into a buffer at from_rate, moved to the same point in time once the
+/// buffer is at to_rate
+fn rescale_offset(offset: usize, from_rate: u32, to_rate: u32) -> usize {
+    (offset as u64 * to_rate as u64 / from_rate.max(1) as u64) as usize
+}
+
+/// How many of the pending samples to flush next: a whole chunk while recording,
+/// everything left once recording has stopped, or None while there's nothing to do
+fn next_flush(pending: usize, chunk_len: usize, recording: bool) -> Option<usize> {
+    let take = if recording {
+        if pending < chunk_len {
+            return None;
+        }
+        chunk_len
+    } else {
+        pending
+    };
+
+    Some(take).filter(|&take| take > 0)
+}
+
+/// Transcribe one chunk of captured audio and append it to the entry
+fn flush_chunk(chunk: &[f32], rate: u32, entry_path: &Path, window: &Window) {
+    let samples = resample(chunk, rate, 16000);
+
+    match transcription::transcribe_audio(&samples) {
+        Ok(text) => match crate::append_to_entry(entry_path, &text) {
+            Ok(()) => {
+                let _ = window.emit("live-dictation-chunk", text);
+            }
+            Err(e) => {
+                let _ = window.emit("live-dictation-error", e);
+            }
+        },
+        // Silent chunks are expected during pauses in speech
+        Err(e) if e == "No speech detected in the audio" => {}
+        Err(e) => {
+            let _ = window.emit("live-dictation-error", e);
+        }
+    }
+}
+
+/// Transcribe the recording in chunks of chunk_secs as it grows, appending each
+/// chunk to the entry as soon as it is done. Exits once the recording stops and
+/// the remaining audio has been flushed.
+pub fn spawn_live_dictation(
+    shared: Arc<SharedSamples>,
+    entry_path: PathBuf,
+    chunk_secs: u32,
+    window: Window,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut offset = 0;
+        let mut offset_rate = 16000;
+
+        loop {
+            thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+            let recording = shared.is_recording();
+
+            let (mut pending, mut rate) = shared.snapshot_from(offset);
+            // The buffer gets resampled to 16kHz when recording stops; keep our offset in step
+            if rate != offset_rate {
+                offset = rescale_offset(offset, offset_rate, rate);
+                offset_rate = rate;
+                (pending, rate) = shared.snapshot_from(offset);
+            }
+
+            let chunk_len = chunk_secs as usize * rate as usize;
+            if let Some(take) = next_flush(pending.len(), chunk_len, recording) {
+                flush_chunk(&pending[..take], rate, &entry_path, &window);
+                offset += take;
+            }
+
+            if !recording {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_a_whole_chunk_while_recording() {
+        assert_eq!(next_flush(0, 48000, true), None);
+        assert_eq!(next_flush(47999, 48000, true), None);
+        assert_eq!(next_flush(48000, 48000, true), Some(48000));
+        // Anything past the chunk waits for the next one
+        assert_eq!(next_flush(70000, 48000, true), Some(48000));
+    }
+
+    #[test]
+    fn flushes_the_remainder_once_stopped() {
+        assert_eq!(next_flush(1234, 48000, false), Some(1234));
+        assert_eq!(next_flush(96000, 48000, false), Some(96000));
+        assert_eq!(next_flush(0, 48000, false), None);
+    }
+
+    #[test]
+    fn offset_follows_the_buffer_to_16khz() {
+        // Two 30-second chunks flushed at 48kHz are 60 seconds into the 16kHz buffer
+        assert_eq!(rescale_offset(2 * 30 * 48000, 48000, 16000), 60 * 16000);
+        assert_eq!(rescale_offset(44100, 44100, 16000), 16000);
+        assert_eq!(rescale_offset(0, 48000, 16000), 0);
+        assert_eq!(rescale_offset(16000, 16000, 16000), 16000);
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{Duration, Local, NaiveDate};
+
+use crate::frontmatter;
+use crate::{
+    entry_date_format, notebook_dir, parse_entry_date, read_notebook_files, unique_filename,
+    write_atomic,
+};
+
+/// First non-empty line of the body, without heading markers
+fn first_line(body: &str) -> Option<String> {
+    body.lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// Build a digest entry for the seven days starting at week_start, linking to each
+/// entry with its title and summary (or first line). The digest is written to the
+/// notebook it covers. Returns the new filename.
+#[tauri::command]
+pub fn generate_weekly_digest(
+    week_start: String,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    let start = parse_entry_date(week_start.trim())
+        .or_else(|| NaiveDate::parse_from_str(week_start.trim(), "%Y-%m-%d").ok())
+        .ok_or("Invalid week start date")?;
+    let end = start + Duration::days(7);
+
+    let mut week_entries = Vec::new();
+
+    for (filename, content) in read_notebook_files(notebook.as_deref())? {
+        let fields = frontmatter::parse(&content);
+
+        // Never fold earlier digests into a new one
+        if frontmatter::get_str(&fields, "digest").as_deref() == Some("true") {
+            continue;
+        }
+
+        let date = match frontmatter::get_str(&fields, "date").and_then(|d| parse_entry_date(&d)) {
+            Some(date) if date >= start && date < end => date,
+            _ => continue,
+        };
+
+        let stem = filename.trim_end_matches(".md").to_string();
+        let title = frontmatter::get_str(&fields, "title")
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| stem.clone());
+        let summary = frontmatter::get_str(&fields, "summary")
+            .filter(|s| !s.is_empty())
+            .or_else(|| first_line(frontmatter::body(&content)));
+
+        week_entries.push((date, stem, title, summary));
+    }
+
+    if week_entries.is_empty() {
+        return Err("No entries found for that week".to_string());
+    }
+
+    week_entries.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+    let title = format!("Week of {}", start.format("%B %-d, %Y"));
+    let mut body = String::new();
+    let mut current_day = None;
+
+    for (date, stem, title, summary) in &week_entries {
+        if current_day != Some(*date) {
+            if current_day.is_some() {
+                body.push('\n');
+            }
+            body.push_str(&format!("## {}\n\n", date.format("%A, %B %-d")));
+            current_day = Some(*date);
+        }
+
+        let link = if title == stem {
+            format!("[[{}]]", stem)
+        } else {
+            format!("[[{}|{}]]", stem, title)
+        };
+
+        match summary {
+            Some(summary) => body.push_str(&format!("- {} — {}\n", link, summary)),
+            None => body.push_str(&format!("- {}\n", link)),
+        }
+    }
+
+    let content = format!(
+        "---\ntitle: {}\ndate: {}\ndigest: true\n---\n\n{}",
+        title,
+        Local::now().format(&entry_date_format()),
+        body
+    );
+
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    let filename = unique_filename(&journal_dir, &title);
+    write_atomic(&journal_dir.join(&filename), &content)?;
+
+    Ok(filename)
+}
@@ -0,0 +1,97 @@
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::EntryInfo;
+
+struct CachedEntry {
+    modified: SystemTime,
+    size_bytes: u64,
+    info: EntryInfo,
+}
+
+// Parsed metadata per entry path, reused until the file's mtime or size changes
+static ENTRY_CACHE: Lazy<Mutex<HashMap<PathBuf, CachedEntry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cached metadata for path if the file is unchanged, otherwise the result of load,
+/// which is remembered for next time
+pub fn entry_info<F>(
+    path: &Path,
+    modified: SystemTime,
+    size_bytes: u64,
+    load: F,
+) -> Option<EntryInfo>
+where
+    F: FnOnce() -> Option<EntryInfo>,
+{
+    if let Ok(cache) = ENTRY_CACHE.lock() {
+        if let Some(cached) = cache.get(path) {
+            if cached.modified == modified && cached.size_bytes == size_bytes {
+                return Some(cached.info.clone());
+            }
+        }
+    }
+
+    let info = load()?;
+
+    if let Ok(mut cache) = ENTRY_CACHE.lock() {
+        cache.insert(
+            path.to_path_buf(),
+            CachedEntry {
+                modified,
+                size_bytes,
+                info: info.clone(),
+            },
+        );
+    }
+
+    Some(info)
+}
+
+/// Drop any cached metadata for path so the next listing re-reads it
+pub fn invalidate(path: &Path) {
+    if let Ok(mut cache) = ENTRY_CACHE.lock() {
+        cache.remove(path);
+    }
+}
+
+/// Forget entries in dir that no longer exist on disk
+pub fn retain_only(dir: &Path, paths: &HashSet<PathBuf>) {
+    if let Ok(mut cache) = ENTRY_CACHE.lock() {
+        cache.retain(|path, _| path.parent() != Some(dir) || paths.contains(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{entry, set_mtime, TestJournal};
+    use std::fs;
+    use std::time::Duration;
+
+    fn titles() -> Vec<String> {
+        crate::list_entries(None)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.title)
+            .collect()
+    }
+
+    #[test]
+    fn changed_mtime_refreshes_the_cache() {
+        let journal = TestJournal::new();
+        let path = journal.write("a.md", &entry("Before", "March 1, 2024", &[], ""));
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(titles(), ["Before"]);
+
+        // Same size and mtime, so the cached metadata is still used
+        fs::write(&path, entry("Edited", "March 1, 2024", &[], "")).unwrap();
+        set_mtime(&path, modified);
+        assert_eq!(titles(), ["Before"]);
+
+        set_mtime(&path, modified + Duration::from_secs(5));
+        assert_eq!(titles(), ["Edited"]);
+    }
+}
@@ -0,0 +1,307 @@
+use chrono::{DateTime, Local};
+use pulldown_cmark::{html, Options, Parser};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::frontmatter;
+use crate::stats::count_words;
+use crate::{
+    get_journal_dir, list_notebooks, notebook_dir, parse_entry_tags, read_journal_files,
+    write_atomic,
+};
+
+#[derive(Serialize)]
+struct ArchivedEntry<'a> {
+    filename: &'a str,
+    frontmatter: frontmatter::Frontmatter,
+    body: &'a str,
+    word_count: usize,
+    mtime: Option<String>,
+    media: Vec<String>,
+}
+
+/// Check that the directory a file will be written into exists
+pub fn ensure_parent_exists(out_path: &Path) -> Result<(), String> {
+    match out_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) if !parent.exists() => {
+            Err(format!("Directory does not exist: {}", parent.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Targets of embedded images and links to audio files in a body
+fn media_references(body: &str) -> Vec<String> {
+    let media_regex = regex::Regex::new(
+        r"(?i)!\[[^\]]*\]\(([^)\s]+)|\]\(([^)\s]+\.(?:m4a|mp3|wav|ogg|flac|aac|mp4|webm))\)",
+    )
+    .unwrap();
+
+    let mut media: Vec<String> = Vec::new();
+    for captures in media_regex.captures_iter(body) {
+        if let Some(m) = captures.get(1).or_else(|| captures.get(2)) {
+            if !media.iter().any(|existing| existing == m.as_str()) {
+                media.push(m.as_str().to_string());
+            }
+        }
+    }
+
+    media
+}
+
+/// Copy every entry carrying the given tag into out_dir, frontmatter included.
+/// Returns the number of entries exported.
+#[tauri::command]
+pub fn export_entries_by_tag(tag: String, out_dir: String) -> Result<usize, String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+
+    let out_dir = Path::new(&out_dir);
+    fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+    let mut exported = 0;
+
+    for (filename, content) in read_journal_files()? {
+        let has_tag = parse_entry_tags(&content)
+            .iter()
+            .any(|t| t.to_lowercase() == tag);
+        if !has_tag {
+            continue;
+        }
+
+        write_atomic(&out_dir.join(&filename), &content)
+            .map_err(|e| format!("Failed to export {}: {}", filename, e))?;
+        exported += 1;
+    }
+
+    Ok(exported)
+}
+
+/// Write the whole journal as a JSON array of full entries, one entry in memory at a time.
+/// Returns the number of entries written.
+#[tauri::command]
+pub fn export_full_json(out_path: String) -> Result<usize, String> {
+    let out_path = Path::new(&out_path);
+    ensure_parent_exists(out_path)?;
+
+    let file =
+        fs::File::create(out_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut writer = BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Failed to write archive: {}", e);
+
+    let journal_dir = get_journal_dir();
+    let mut paths: Vec<_> = fs::read_dir(&journal_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("md"))
+        .collect();
+    paths.sort();
+
+    writer.write_all(b"[\n").map_err(write_err)?;
+    let mut written = 0;
+
+    for path in paths {
+        let (Some(filename), Ok(content)) = (
+            path.file_name().and_then(|s| s.to_str()),
+            fs::read_to_string(&path),
+        ) else {
+            continue;
+        };
+
+        let body = frontmatter::body(&content);
+        let mtime = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(|t| DateTime::<Local>::from(t).to_rfc3339());
+
+        let archived = ArchivedEntry {
+            filename,
+            frontmatter: frontmatter::parse(&content),
+            body,
+            word_count: count_words(body),
+            mtime,
+            media: media_references(body),
+        };
+
+        if written > 0 {
+            writer.write_all(b",\n").map_err(write_err)?;
+        }
+        serde_json::to_writer(&mut writer, &archived).map_err(|e| e.to_string())?;
+        written += 1;
+    }
+
+    writer.write_all(b"\n]\n").map_err(write_err)?;
+    writer.flush().map_err(write_err)?;
+
+    Ok(written)
+}
+
+/// .md files directly inside dir, as (name in the archive, path) sorted by name
+fn markdown_files(dir: &Path, prefix: &str) -> Result<Vec<(String, std::path::PathBuf)>, String> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md"))
+        .filter_map(|path| {
+            let filename = path.file_name()?.to_str()?.to_string();
+            Some((format!("{}{}", prefix, filename), path))
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Write every entry, notebooks included, into a zip at out_path. Entries keep
+/// their filenames; those in a notebook sit in a folder named after it. Trash,
+/// history, templates and models are left out. Returns the number of files written.
+#[tauri::command]
+pub fn export_all_zip(out_path: String) -> Result<usize, String> {
+    let out_path = Path::new(&out_path);
+    ensure_parent_exists(out_path)?;
+
+    let journal_dir = get_journal_dir();
+    let mut files = markdown_files(&journal_dir, "")?;
+    for notebook in list_notebooks()? {
+        files.extend(markdown_files(
+            &journal_dir.join(&notebook),
+            &format!("{}/", notebook),
+        )?);
+    }
+
+    let file =
+        fs::File::create(out_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = zip::ZipWriter::new(BufWriter::new(file));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut written = 0;
+
+    for (name, path) in files {
+        // Copied straight from disk so only one buffer's worth of an entry is in memory
+        let mut entry =
+            fs::File::open(&path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        io::copy(&mut entry, &mut zip).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+        written += 1;
+    }
+
+    let mut writer = zip
+        .finish()
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+
+    Ok(written)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render an entry as a standalone HTML page, titled from its frontmatter
+#[tauri::command]
+pub fn export_entry_html(filename: String, notebook: Option<String>) -> Result<String, String> {
+    let content = fs::read_to_string(notebook_dir(notebook.as_deref())?.join(&filename))
+        .map_err(|e| e.to_string())?;
+
+    let (title, _) = crate::parse_frontmatter(&content);
+    let title = if title.is_empty() {
+        filename.trim_end_matches(".md").to_string()
+    } else {
+        title
+    };
+
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut body_html = String::new();
+    html::push_html(
+        &mut body_html,
+        Parser::new_ext(frontmatter::body(&content), options),
+    );
+
+    let title = escape_html(&title);
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n{}</body>\n</html>\n",
+        title, title, body_html
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TestJournal};
+    use std::io::Read;
+
+    #[test]
+    fn html_keeps_headings_and_lists() {
+        let journal = TestJournal::new();
+        journal.write(
+            "plans.md",
+            &entry(
+                "Tom & Jerry",
+                "March 1, 2024",
+                &[],
+                "## Plans\n\n- one\n- two\n",
+            ),
+        );
+
+        let html = export_entry_html("plans.md".into(), None).unwrap();
+
+        assert!(html.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(html.contains("<h2>Plans</h2>"));
+        assert!(html.contains("<ul>\n<li>one</li>\n<li>two</li>\n</ul>"));
+        assert!(!html.contains("date:"));
+    }
+
+    #[test]
+    fn zip_holds_every_entry_but_the_trash() {
+        let journal = TestJournal::new();
+        let first = entry("First", "March 1, 2024", &[], "One.");
+        journal.write("first.md", &first);
+        journal.write("second.md", &entry("Second", "March 2, 2024", &[], "Two."));
+        journal.write(
+            "Work/standup.md",
+            &entry("Standup", "March 3, 2024", &[], ""),
+        );
+        journal.write(".trash/old.md", &entry("Old", "March 4, 2024", &[], ""));
+        journal.write("models/ggml-tiny.bin", "lmgg");
+
+        let out = journal.outside("backup.zip");
+        let written = export_all_zip(out.to_string_lossy().into_owned()).unwrap();
+        assert_eq!(written, 3);
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&out).unwrap()).unwrap();
+        assert_eq!(archive.len(), 3);
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, ["Work/standup.md", "first.md", "second.md"]);
+
+        let mut content = String::new();
+        archive
+            .by_name("first.md")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, first);
+    }
+
+    #[test]
+    fn zip_needs_an_existing_folder() {
+        let journal = TestJournal::new();
+        let out = journal.outside("missing/backup.zip");
+
+        let err = export_all_zip(out.to_string_lossy().into_owned()).unwrap_err();
+        assert!(err.starts_with("Directory does not exist"));
+    }
+}
@@ -0,0 +1,379 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Parsed frontmatter fields, keyed by name
+pub type Frontmatter = BTreeMap<String, Value>;
+
+// Opening delimiter, optional YAML block, closing delimiter. The block group is
+// skipped for an empty `---\n---` header.
+static BLOCK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)^---\r?\n(?:(.*?)\r?\n)?---[ \t]*(?:\r?\n|$)").unwrap());
+
+// Fields written first, in this order, so headers stay readable and the
+// frontend's `title:`/`date:` lookups keep matching
+const LEADING_KEYS: &[&str] = &["title", "date"];
+
+/// Split content into its raw frontmatter block (if any) and the body after it
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    match BLOCK_REGEX.captures(content) {
+        Some(caps) => {
+            let block = caps.get(1).map_or("", |m| m.as_str());
+            let end = caps.get(0).map_or(0, |m| m.end());
+            (Some(block), &content[end..])
+        }
+        None => (None, content),
+    }
+}
+
+/// The entry body with any frontmatter block removed
+pub fn body(content: &str) -> &str {
+    split(content).1
+}
+
+pub fn has_frontmatter(content: &str) -> bool {
+    split(content).0.is_some()
+}
+
+/// Line-based fallback for headers that aren't valid YAML, e.g. an unquoted
+/// title containing `: `. Every top-level `key: value` line becomes a string.
+fn parse_lines(block: &str) -> Frontmatter {
+    block
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+
+            let value = value.trim();
+            let value = if value.is_empty() {
+                Value::Null
+            } else {
+                Value::String(value.to_string())
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Parse a raw frontmatter block (without delimiters) into its fields
+pub fn parse_block(block: &str) -> Frontmatter {
+    if block.trim().is_empty() {
+        return Frontmatter::new();
+    }
+
+    serde_yaml::from_str(block).unwrap_or_else(|_| parse_lines(block))
+}
+
+/// All frontmatter fields of an entry's content; empty when there is no header
+pub fn parse(content: &str) -> Frontmatter {
+    split(content).0.map(parse_block).unwrap_or_default()
+}
+
+/// A scalar field as text. Null, missing and non-scalar values give None.
+pub fn get_str(frontmatter: &Frontmatter, key: &str) -> Option<String> {
+    match frontmatter.get(key)? {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A list field, accepting a YAML sequence, a `[a, b]` string or an inline `a, b` string
+pub fn get_list(frontmatter: &Frontmatter, key: &str) -> Vec<String> {
+    match frontmatter.get(key) {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(s) => Some(s.trim().to_string()),
+                Value::Number(n) => Some(n.to_string()),
+                Value::Bool(b) => Some(b.to_string()),
+                _ => None,
+            })
+            .filter(|item| !item.is_empty())
+            .collect(),
+        Some(Value::String(s)) => s
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+            .map(|item| {
+                item.trim()
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .to_string()
+            })
+            .filter(|item| !item.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a string can be written unquoted and read back unchanged
+fn is_plain_safe(value: &str) -> bool {
+    if value.contains(['\n', '\r']) {
+        return false;
+    }
+
+    serde_yaml::from_str::<Frontmatter>(&format!("k: {}", value))
+        .map(|parsed| parsed.get("k") == Some(&Value::String(value.to_string())))
+        .unwrap_or(false)
+}
+
+fn render_field(key: &str, value: &Value) -> String {
+    match value {
+        Value::Null => format!("{}:\n", key),
+        Value::String(s) if s.is_empty() => format!("{}:\n", key),
+        Value::String(s) if is_plain_safe(s) => format!("{}: {}\n", key, s),
+        _ => {
+            let mut single = Frontmatter::new();
+            single.insert(key.to_string(), value.clone());
+            serde_yaml::to_string(&single).unwrap_or_else(|_| format!("{}:\n", key))
+        }
+    }
+}
+
+/// Render fields and body back into entry content. Title and date come first;
+/// other fields follow in key order.
+pub fn render(frontmatter: &Frontmatter, body: &str) -> String {
+    let mut out = String::from("---\n");
+
+    for key in LEADING_KEYS {
+        if let Some(value) = frontmatter.get(*key) {
+            out.push_str(&render_field(key, value));
+        }
+    }
+
+    for (key, value) in frontmatter {
+        if !LEADING_KEYS.contains(&key.as_str()) {
+            out.push_str(&render_field(key, value));
+        }
+    }
+
+    out.push_str("---\n");
+    out.push_str(body);
+    out
+}
+
+/// Where the raw block sits in content. An empty header gives an empty range just
+/// after its opening line.
+fn block_range(content: &str) -> Option<Range<usize>> {
+    let caps = BLOCK_REGEX.captures(content)?;
+    Some(match caps.get(1) {
+        Some(block) => block.range(),
+        None => {
+            let open = content.find('\n').map_or(0, |i| i + 1);
+            open..open
+        }
+    })
+}
+
+/// Writes patch the raw block, so it has to be real YAML: re-rendering fields from
+/// the line-based fallback would drop nested and list values
+fn check_block(block: &str) -> Result<(), String> {
+    if block.trim().is_empty() {
+        return Ok(());
+    }
+
+    serde_yaml::from_str::<Frontmatter>(block)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid frontmatter, left unchanged: {}", e))
+}
+
+fn is_key_line(line: &str, key: &str) -> bool {
+    line.strip_prefix(key)
+        .is_some_and(|rest| rest.trim_start_matches([' ', '\t']).starts_with(':'))
+}
+
+/// Byte range of a top-level field in block (with a trailing newline): its `key:`
+/// line plus the indented or `- ` lines that continue its value
+fn field_range(block: &str, key: &str) -> Option<Range<usize>> {
+    let mut start = None;
+    let mut end = 0;
+    let mut offset = 0;
+
+    for line in block.split_inclusive('\n') {
+        let line_end = offset + line.len();
+        match start {
+            None if is_key_line(line, key) => {
+                start = Some(offset);
+                end = line_end;
+            }
+            None => {}
+            // Blank lines only belong to the value if more of it follows
+            Some(_) if line.trim().is_empty() => {}
+            Some(_) if line.starts_with(char::is_whitespace) || line.starts_with('-') => {
+                end = line_end;
+            }
+            Some(_) => break,
+        }
+        offset = line_end;
+    }
+
+    start.map(|start| start..end)
+}
+
+/// Where a new field goes in block: title and date at the top in LEADING_KEYS
+/// order, anything else at the end
+fn insert_position(block: &str, key: &str) -> usize {
+    match LEADING_KEYS.iter().position(|k| *k == key) {
+        Some(index) => LEADING_KEYS[..index]
+            .iter()
+            .filter_map(|k| field_range(block, k))
+            .map(|range| range.end)
+            .max()
+            .unwrap_or(0),
+        None => block.len(),
+    }
+}
+
+/// Apply edit to the raw block of content (given a trailing newline) and put it back
+fn patch_block<F>(content: &str, range: Range<usize>, edit: F) -> Result<String, String>
+where
+    F: FnOnce(&mut String),
+{
+    let raw = &content[range.clone()];
+    check_block(raw)?;
+
+    // An empty header has no line of its own for the block, so it keeps the newline
+    let mut block = format!("{}\n", raw);
+    if raw.is_empty() {
+        block.clear();
+    }
+    edit(&mut block);
+    if !raw.is_empty() && block.ends_with('\n') {
+        block.pop();
+    }
+
+    Ok(format!(
+        "{}{}{}",
+        &content[..range.start],
+        block,
+        &content[range.end..]
+    ))
+}
+
+/// Set a single text field, keeping every other line of the header and the body
+/// byte for byte. Content without frontmatter gets a new block.
+pub fn set_field(content: &str, key: &str, value: &str) -> Result<String, String> {
+    set_value(content, key, Value::String(value.to_string()))
+}
+
+/// Like set_field, for any YAML value
+pub fn set_value(content: &str, key: &str, value: Value) -> Result<String, String> {
+    let field = render_field(key, &value);
+
+    let Some(range) = block_range(content) else {
+        return Ok(format!("---\n{}---\n\n{}", field, content));
+    };
+
+    patch_block(content, range, |block| match field_range(block, key) {
+        Some(existing) => block.replace_range(existing, &field),
+        None => block.insert_str(insert_position(block, key), &field),
+    })
+}
+
+/// Remove a field, keeping every other line of the header and the body byte for byte
+pub fn remove_field(content: &str, key: &str) -> Result<String, String> {
+    let Some(range) = block_range(content) else {
+        return Ok(content.to_string());
+    };
+
+    patch_block(content, range, |block| {
+        if let Some(existing) = field_range(block, key) {
+            block.replace_range(existing, "");
+        }
+    })
+}
+
+/// content with its body replaced, keeping the frontmatter block as it is
+pub fn with_body(content: &str, body: &str) -> String {
+    let header_len = content.len() - split(content).1.len();
+    format!("{}{}", &content[..header_len], body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_a_field_leaves_other_lines_alone() {
+        let content =
+            "---\ntitle: Trip\n# private\nzeta: 1\ntags:\n  - a\n  - b\nalpha: x\n---\n\nBody";
+        let tags = Value::Sequence(vec![Value::String("c".into())]);
+
+        assert_eq!(
+            set_value(content, "tags", tags).unwrap(),
+            "---\ntitle: Trip\n# private\nzeta: 1\ntags:\n- c\nalpha: x\n---\n\nBody"
+        );
+        assert_eq!(
+            set_field(content, "zeta", "2").unwrap(),
+            content.replace("zeta: 1", "zeta: 2")
+        );
+    }
+
+    #[test]
+    fn new_fields_go_where_they_belong() {
+        assert_eq!(
+            set_field(
+                "---\ntitle: A\nmood: ok\n---\nBody",
+                "date",
+                "March 1, 2024"
+            )
+            .unwrap(),
+            "---\ntitle: A\ndate: March 1, 2024\nmood: ok\n---\nBody"
+        );
+        assert_eq!(
+            set_field("---\ndate: March 1, 2024\n---\nBody", "title", "A").unwrap(),
+            "---\ntitle: A\ndate: March 1, 2024\n---\nBody"
+        );
+        assert_eq!(
+            set_value("---\ntitle: A\n---\nBody", "pinned", Value::Bool(true)).unwrap(),
+            "---\ntitle: A\npinned: true\n---\nBody"
+        );
+        assert_eq!(
+            set_value("---\n---\nBody", "pinned", Value::Bool(true)).unwrap(),
+            "---\npinned: true\n---\nBody"
+        );
+        assert_eq!(
+            set_value("Body", "pinned", Value::Bool(true)).unwrap(),
+            "---\npinned: true\n---\n\nBody"
+        );
+    }
+
+    #[test]
+    fn removing_a_field_keeps_the_rest() {
+        assert_eq!(
+            remove_field("---\ntitle: A\nmood: ok\n# keep\n---\nB", "mood").unwrap(),
+            "---\ntitle: A\n# keep\n---\nB"
+        );
+        assert_eq!(remove_field("B", "mood").unwrap(), "B");
+    }
+
+    #[test]
+    fn invalid_yaml_is_never_rewritten() {
+        let content = "---\ntitle: Meeting: notes\ntags:\n  - a\n---\nBody";
+
+        // Reads fall back to the line parser; writes refuse rather than lose `tags`
+        assert_eq!(
+            get_str(&parse(content), "title").as_deref(),
+            Some("Meeting: notes")
+        );
+        assert!(set_field(content, "date", "March 1, 2024").is_err());
+        assert!(remove_field(content, "mood").is_err());
+    }
+
+    #[test]
+    fn replacing_the_body_keeps_the_header() {
+        assert_eq!(
+            with_body("---\ntitle: A\n---\n\nOld", "\nNew"),
+            "---\ntitle: A\n---\n\nNew"
+        );
+        assert_eq!(with_body("Old", "New"), "New");
+    }
+}
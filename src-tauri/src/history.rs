@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{entry_key, notebook_dir, write_atomic};
+
+// One undo snapshot per entry: the content it had before its latest save, kept
+// under the entry's key (e.g. "Work/x.md"). A new save replaces the snapshot.
+pub const HISTORY_DIR: &str = ".history";
+
+fn snapshot_path(filename: &str) -> PathBuf {
+    crate::get_journal_dir().join(HISTORY_DIR).join(filename)
+}
+
+/// Keep the current content of an entry as its undo snapshot before it is overwritten.
+/// Saves that don't change anything leave the existing snapshot alone.
+pub fn snapshot_before_save(path: &Path, filename: &str, new_content: &str) -> Result<(), String> {
+    let current = match fs::read_to_string(path) {
+        Ok(current) => current,
+        Err(_) => return Ok(()),
+    };
+
+    if current == new_content {
+        return Ok(());
+    }
+
+    let snapshot = snapshot_path(filename);
+    if let Some(parent) = snapshot.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    write_atomic(&snapshot, &current)
+}
+
+/// Carry an entry's snapshot over when the entry is renamed
+pub fn rename_snapshot(old_filename: &str, new_filename: &str) {
+    let old_snapshot = snapshot_path(old_filename);
+    if old_snapshot.exists() {
+        fs::rename(&old_snapshot, snapshot_path(new_filename)).ok();
+    }
+}
+
+/// Restore the content an entry (in a notebook, or the top-level journal when None)
+/// had before its most recent save and return it. The snapshot is consumed, so
+/// undoing twice reports there is nothing left to undo.
+#[tauri::command]
+pub fn undo_last_save(filename: String, notebook: Option<String>) -> Result<String, String> {
+    let notebook = notebook.as_deref();
+    let snapshot = snapshot_path(&entry_key(notebook, &filename));
+    if !snapshot.exists() {
+        return Err("No previous save to restore".to_string());
+    }
+
+    let entry_path = notebook_dir(notebook)?.join(&filename);
+    if !entry_path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    let restored = fs::read_to_string(&snapshot).map_err(|e| e.to_string())?;
+    write_atomic(&entry_path, &restored)?;
+    fs::remove_file(&snapshot).map_err(|e| e.to_string())?;
+
+    Ok(restored)
+}
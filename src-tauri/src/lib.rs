@@ -1,14 +1,38 @@
+mod audio_analysis;
 mod audio_import;
 mod audio_recorder;
+mod dictation;
+mod digest;
+mod entry_cache;
+mod export;
+mod frontmatter;
+mod history;
+mod links;
+mod long_transcription;
+mod maintenance;
+mod markdown_import;
+mod pdf_export;
+mod search;
+mod settings;
+mod stats;
+mod templates;
+#[cfg(test)]
+mod test_support;
+mod transcript_export;
 mod transcription;
+mod transliteration;
+mod trash;
+mod watcher;
 pub mod whisper_model;
 
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use chrono::Local;
-use serde::{Serialize, Deserialize};
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
 
 use audio_recorder::SharedSamples;
 use std::sync::Arc;
@@ -18,48 +42,444 @@ use std::thread::JoinHandle;
 pub struct RecorderState {
     pub shared: Arc<SharedSamples>,
     pub handle: Mutex<Option<JoinHandle<()>>>,
+    pub dictation: Mutex<Option<JoinHandle<()>>>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
+struct SampleTranscription {
+    text: String,
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ImportProbe {
+    duration_secs: f32,
+    peak: f32,
+    rms: f32,
+    has_audio: bool,
+}
+
+#[derive(Serialize)]
+struct BatchResult {
+    filename: String,
+    transcript: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct BatchProgress {
+    index: usize,
+    total: usize,
+    filename: String,
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+struct ResamplePreview {
+    from_rate: u32,
+    to_rate: u32,
+    original_peaks: Vec<f32>,
+    resampled_peaks: Vec<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 struct EntryInfo {
     filename: String,
     title: String,
     date: String,
+    /// Language of the entry's text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// Language spoken in the source audio, when it differs from the text (e.g. translated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spoken_language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mood: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    #[serde(default)]
+    size_bytes: u64,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    pinned: bool,
+    /// Start of the body as plain text, for previews in the list
+    #[serde(default)]
+    excerpt: String,
 }
 
-fn get_journal_dir() -> PathBuf {
+fn default_journal_dir() -> PathBuf {
     let home = dirs::home_dir().expect("Could not find home directory");
-    home.join("Documents").join("Project Data Files").join("Journal")
+    home.join("Documents")
+        .join("Project Data Files")
+        .join("Journal")
 }
 
-fn parse_frontmatter(content: &str) -> (String, String) {
-    let frontmatter_regex = regex::Regex::new(r"(?s)^---\n(.*?)\n---").unwrap();
+/// The configured journal folder, or the default when none is set
+fn get_journal_dir() -> PathBuf {
+    settings::load_settings()
+        .journal_dir
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(default_journal_dir)
+}
 
-    if let Some(captures) = frontmatter_regex.captures(content) {
-        let frontmatter = captures.get(1).map_or("", |m| m.as_str());
+/// Point the journal at a different folder, creating it if needed, and watch it
+/// instead of the old one. An empty path goes back to the default location.
+#[tauri::command]
+fn set_journal_dir(
+    app: AppHandle,
+    journal_watcher: State<watcher::JournalWatcher>,
+    path: String,
+) -> Result<(), String> {
+    let path = path.trim();
 
-        let title_regex = regex::Regex::new(r"(?m)^title:\s*(.*)$").unwrap();
-        let date_regex = regex::Regex::new(r"(?m)^date:\s*(.*)$").unwrap();
+    if path.is_empty() {
+        settings::update_settings(|s| s.journal_dir = None)?;
+    } else {
+        let dir = PathBuf::from(path);
+        if dir.exists() && !dir.is_dir() {
+            return Err(format!("Not a directory: {}", path));
+        }
 
-        let title = title_regex.captures(frontmatter)
-            .and_then(|c| c.get(1))
-            .map_or("", |m| m.as_str().trim())
-            .to_string();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create journal directory: {}", e))?;
+        settings::update_settings(|s| s.journal_dir = Some(path.to_string()))?;
+    }
 
-        let date = date_regex.captures(frontmatter)
-            .and_then(|c| c.get(1))
-            .map_or("", |m| m.as_str().trim())
-            .to_string();
+    // Replacing the watcher drops the old one, which ends its debounce thread
+    if let Err(e) = watcher::start_watching(&app, &journal_watcher) {
+        eprintln!("Failed to watch journal directory: {}", e);
+    }
+    Ok(())
+}
 
-        (title, date)
-    } else {
-        (String::new(), String::new())
+// Journal subfolders the app uses for its own files rather than for a notebook
+const RESERVED_DIRS: &[&str] = &["models", templates::TEMPLATES_DIR];
+
+/// Check a notebook name is a single plain folder name inside the journal
+fn validate_notebook_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+
+    if name.is_empty()
+        || name.starts_with('.')
+        || sanitize_filename(name) != name
+        || RESERVED_DIRS.iter().any(|r| r.eq_ignore_ascii_case(name))
+    {
+        return Err(format!("Invalid notebook name: {}", name));
+    }
+
+    Ok(name)
+}
+
+/// Folder holding a notebook's entries; None (or an empty name) is the journal root
+fn notebook_dir(notebook: Option<&str>) -> Result<PathBuf, String> {
+    let root = get_journal_dir();
+
+    match notebook.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => Ok(root.join(validate_notebook_name(name)?)),
+        None => Ok(root),
+    }
+}
+
+/// An entry's path relative to the journal root, used to key per-entry history
+fn entry_key(notebook: Option<&str>, filename: &str) -> String {
+    match notebook.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => format!("{}/{}", name, filename),
+        None => filename.to_string(),
     }
 }
 
+/// Notebooks in the journal, i.e. its non-hidden subfolders
 #[tauri::command]
-fn list_entries() -> Result<Vec<EntryInfo>, String> {
+fn list_notebooks() -> Result<Vec<String>, String> {
     let journal_dir = get_journal_dir();
+    if !journal_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut notebooks: Vec<String> = fs::read_dir(&journal_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .filter(|name| validate_notebook_name(name).is_ok())
+        .collect();
+
+    notebooks.sort_by_key(|name| name.to_lowercase());
+    Ok(notebooks)
+}
+
+#[tauri::command]
+fn create_notebook(name: String) -> Result<(), String> {
+    let dir = notebook_dir(Some(&name))?;
+    if dir.exists() {
+        return Err("A notebook with that name already exists".to_string());
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create notebook: {}", e))
+}
+
+#[tauri::command]
+fn get_journal_dir_setting() -> Result<String, String> {
+    Ok(get_journal_dir().to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+fn get_date_format() -> Result<String, String> {
+    Ok(entry_date_format())
+}
+
+/// Change the date format for new entries. The format must produce a usable
+/// filename and parse back to the same date so entries keep sorting.
+#[tauri::command]
+fn set_date_format(format: String) -> Result<(), String> {
+    use std::fmt::Write;
+
+    let format = format.trim();
+    let sample = NaiveDate::from_ymd_opt(2024, 3, 15).ok_or("Invalid sample date")?;
+
+    let mut formatted = String::new();
+    if write!(formatted, "{}", sample.format(format)).is_err() || formatted.trim().is_empty() {
+        return Err(format!("Invalid date format: {}", format));
+    }
+    if sanitize_filename(&formatted) != formatted {
+        return Err(format!(
+            "Date format produces an invalid filename: {}",
+            formatted
+        ));
+    }
+    if NaiveDate::parse_from_str(&formatted, format).ok() != Some(sample) {
+        return Err(format!(
+            "Date format must include the day, month and year: {}",
+            format
+        ));
+    }
+
+    settings::update_settings(|s| s.date_format = format.to_string())?;
+    Ok(())
+}
+
+/// Pick a filename in dir for stem that doesn't collide, appending " (2)", " (3)", ...
+fn unique_filename(dir: &std::path::Path, stem: &str) -> String {
+    let mut candidate = format!("{}.md", stem);
+    let mut n = 2;
+
+    while dir.join(&candidate).exists() {
+        candidate = format!("{} ({}).md", stem, n);
+        n += 1;
+    }
+
+    candidate
+}
+
+/// Write via a temporary file in the same directory and rename it over the target,
+/// so a crash mid-write never leaves a truncated file
+fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid file path")?;
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    entry_cache::invalidate(path);
+
+    if let Err(e) = fs::write(&temp_path, content) {
+        fs::remove_file(&temp_path).ok();
+        return Err(e.to_string());
+    }
+
+    if let Err(e) = fs::rename(&temp_path, path) {
+        fs::remove_file(&temp_path).ok();
+        return Err(e.to_string());
+    }
+
+    Ok(())
+}
+
+/// Atomically append text to the end of an entry
+fn append_to_entry(path: &Path, text: &str) -> Result<(), String> {
+    let mut content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    if !content.is_empty() && !content.ends_with(char::is_whitespace) {
+        content.push(' ');
+    }
+    content.push_str(text.trim());
+
+    write_atomic(path, &content)
+}
+
+/// Read every .md entry in the journal root as (filename, content) pairs, sorted by filename
+fn read_journal_files() -> Result<Vec<(String, String)>, String> {
+    read_notebook_files(None)
+}
+
+/// Read every .md entry in a notebook (None for the journal root), sorted by filename
+fn read_notebook_files(notebook: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let journal_dir = notebook_dir(notebook)?;
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(&journal_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("md") {
+            continue;
+        }
+
+        if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
+            if let Ok(content) = fs::read_to_string(&path) {
+                files.push((filename.to_string(), content));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Configured format for the dates of new entries
+fn entry_date_format() -> String {
+    settings::load_settings().date_format
+}
+
+/// Parse a frontmatter date with the given format, falling back to the formats
+/// entries were written in before the format became configurable
+fn parse_entry_date_with(date_str: &str, format: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date_str, format)
+        .or_else(|_| NaiveDate::parse_from_str(date_str, "%B %-d, %Y"))
+        .or_else(|_| NaiveDate::parse_from_str(date_str, "%B %d, %Y"))
+        .ok()
+}
+
+/// Parse a frontmatter date, trying each supported format
+fn parse_entry_date(date_str: &str) -> Option<NaiveDate> {
+    parse_entry_date_with(date_str, &entry_date_format())
+}
+
+fn parse_frontmatter(content: &str) -> (String, String) {
+    let fields = frontmatter::parse(content);
+    let title = frontmatter::get_str(&fields, "title").unwrap_or_default();
+    let date = frontmatter::get_str(&fields, "date").unwrap_or_default();
+
+    (title, date)
+}
+
+fn parse_entry_tags(content: &str) -> Vec<String> {
+    frontmatter::get_list(&frontmatter::parse(content), "tags")
+}
+
+// Characters of body text kept in an entry's excerpt
+const EXCERPT_CHARS: usize = 160;
+
+/// The first EXCERPT_CHARS characters of the body's text, with markdown syntax
+/// dropped and whitespace collapsed. Longer bodies are cut at a word and get "…".
+fn make_excerpt(content: &str) -> String {
+    use pulldown_cmark::{Event, Parser, TagEnd};
+
+    let mut text = String::new();
+    for event in Parser::new(frontmatter::body(content)) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            // Inline markup sits inside words and sentences, so no gap after it
+            Event::End(
+                TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Link
+                | TagEnd::Image,
+            ) => {}
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= EXCERPT_CHARS {
+        return text;
+    }
+
+    let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+    let cut = cut
+        .rsplit_once(' ')
+        .map_or(cut.as_str(), |(words, _)| words);
+    format!("{}…", cut.trim_end())
+}
+
+fn build_entry_info(filename: &str, content: &str, size_bytes: u64) -> EntryInfo {
+    let (title, date) = parse_frontmatter(content);
+    let fields = frontmatter::parse(content);
+    let optional_field = |key: &str| frontmatter::get_str(&fields, key).filter(|v| !v.is_empty());
+
+    EntryInfo {
+        filename: filename.to_string(),
+        title,
+        date,
+        language: optional_field("language"),
+        spoken_language: optional_field("spoken_language"),
+        mood: optional_field("mood"),
+        location: optional_field("location"),
+        size_bytes,
+        tags: frontmatter::get_list(&fields, "tags"),
+        pinned: frontmatter::get_str(&fields, "pinned").as_deref() == Some("true"),
+        excerpt: make_excerpt(content),
+    }
+}
+
+/// Orderings the frontend can ask list_entries_sorted for
+#[derive(Deserialize, Clone, Copy, Default)]
+enum SortSpec {
+    #[default]
+    DateDesc,
+    DateAsc,
+    TitleAsc,
+    TitleDesc,
+    FilenameAsc,
+}
+
+/// Newest first, keeping entries without a parseable date together
+fn compare_by_date(a: &EntryInfo, b: &EntryInfo, format: &str) -> std::cmp::Ordering {
+    match (
+        parse_entry_date_with(&b.date, format),
+        parse_entry_date_with(&a.date, format),
+    ) {
+        (Some(date_b), Some(date_a)) => date_b.cmp(&date_a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.filename.cmp(&a.filename),
+    }
+}
+
+fn compare_by_title(a: &EntryInfo, b: &EntryInfo) -> std::cmp::Ordering {
+    a.title
+        .to_lowercase()
+        .cmp(&b.title.to_lowercase())
+        .then_with(|| a.filename.cmp(&b.filename))
+}
+
+fn sort_entries(entries: &mut [EntryInfo], sort: SortSpec) {
+    let format = entry_date_format();
+
+    match sort {
+        SortSpec::DateDesc => entries.sort_by(|a, b| compare_by_date(a, b, &format)),
+        SortSpec::DateAsc => entries.sort_by(|a, b| compare_by_date(b, a, &format)),
+        SortSpec::TitleAsc => entries.sort_by(compare_by_title),
+        SortSpec::TitleDesc => entries.sort_by(|a, b| compare_by_title(b, a)),
+        SortSpec::FilenameAsc => entries.sort_by(|a, b| a.filename.cmp(&b.filename)),
+    }
+
+    // Pinned entries come first; the sort is stable so each group keeps its order
+    entries.sort_by_key(|e| !e.pinned);
+}
+
+#[tauri::command]
+fn list_entries(notebook: Option<String>) -> Result<Vec<EntryInfo>, String> {
+    list_entries_sorted(SortSpec::default(), notebook)
+}
+
+#[tauri::command]
+fn list_entries_sorted(sort: SortSpec, notebook: Option<String>) -> Result<Vec<EntryInfo>, String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
 
     // Create directory if it doesn't exist
     if !journal_dir.exists() {
@@ -67,6 +487,7 @@ fn list_entries() -> Result<Vec<EntryInfo>, String> {
     }
 
     let mut entries = Vec::new();
+    let mut seen = HashSet::new();
 
     match fs::read_dir(&journal_dir) {
         Ok(dir) => {
@@ -75,15 +496,24 @@ fn list_entries() -> Result<Vec<EntryInfo>, String> {
                     let path = entry.path();
                     if path.extension().and_then(|s| s.to_str()) == Some("md") {
                         if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-                            // Read file to get metadata
-                            if let Ok(content) = fs::read_to_string(&path) {
-                                let (title, date) = parse_frontmatter(&content);
-                                entries.push(EntryInfo {
-                                    filename: filename.to_string(),
-                                    title,
-                                    date,
+                            let metadata = match entry.metadata() {
+                                Ok(metadata) => metadata,
+                                Err(_) => continue,
+                            };
+                            let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+
+                            // Only re-read files that changed since they were last parsed
+                            let info =
+                                entry_cache::entry_info(&path, modified, metadata.len(), || {
+                                    fs::read_to_string(&path).ok().map(|content| {
+                                        build_entry_info(filename, &content, metadata.len())
+                                    })
                                 });
+
+                            if let Some(info) = info {
+                                entries.push(info);
                             }
+                            seen.insert(path);
                         }
                     }
                 }
@@ -92,211 +522,1312 @@ fn list_entries() -> Result<Vec<EntryInfo>, String> {
         Err(e) => return Err(e.to_string()),
     }
 
-    // Sort entries by date (newest first)
-    entries.sort_by(|a, b| {
-        // Try to parse dates for proper sorting
-        use chrono::NaiveDate;
-
-        let parse_date = |date_str: &str| -> Option<NaiveDate> {
-            // Try multiple date formats
-            NaiveDate::parse_from_str(date_str, "%B %-d, %Y")
-                .or_else(|_| NaiveDate::parse_from_str(date_str, "%B %d, %Y"))
-                .ok()
-        };
+    entry_cache::retain_only(&journal_dir, &seen);
 
-        match (parse_date(&b.date), parse_date(&a.date)) {
-            (Some(date_b), Some(date_a)) => date_b.cmp(&date_a),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => b.filename.cmp(&a.filename),
-        }
-    });
+    sort_entries(&mut entries, sort);
 
     Ok(entries)
 }
 
+/// Entries of at least min_bytes, largest first
 #[tauri::command]
-fn read_entry(filename: String) -> Result<String, String> {
-    let journal_dir = get_journal_dir();
-    let file_path = journal_dir.join(&filename);
+fn list_large_entries(min_bytes: u64, notebook: Option<String>) -> Result<Vec<EntryInfo>, String> {
+    let mut entries: Vec<EntryInfo> = list_entries(notebook)?
+        .into_iter()
+        .filter(|e| e.size_bytes >= min_bytes)
+        .collect();
 
-    fs::read_to_string(&file_path).map_err(|e| e.to_string())
+    entries.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+    Ok(entries)
 }
 
+/// Entries carrying the given tag, compared case-insensitively
 #[tauri::command]
-fn save_entry(filename: String, content: String) -> Result<(), String> {
-    let journal_dir = get_journal_dir();
-
-    // Create directory if it doesn't exist
-    if !journal_dir.exists() {
-        fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
-    }
+fn list_entries_by_tag(tag: String, notebook: Option<String>) -> Result<Vec<EntryInfo>, String> {
+    let tag = tag.trim().to_lowercase();
 
-    let file_path = journal_dir.join(&filename);
-    fs::write(&file_path, content).map_err(|e| e.to_string())
+    Ok(list_entries(notebook)?
+        .into_iter()
+        .filter(|e| e.tags.iter().any(|t| t.to_lowercase() == tag))
+        .collect())
 }
 
+/// Every tag used in the journal, deduplicated case-insensitively and sorted
 #[tauri::command]
-fn create_entry() -> Result<String, String> {
-    let now = Local::now();
-    let date_string = now.format("%B %-d, %Y").to_string();
-    let filename = format!("{}.md", date_string);
+fn list_all_tags(notebook: Option<String>) -> Result<Vec<String>, String> {
+    // Keyed by lowercase so "Work" and "work" collapse; the first spelling seen wins
+    let mut tags: BTreeMap<String, String> = BTreeMap::new();
 
-    let journal_dir = get_journal_dir();
-
-    // Create directory if it doesn't exist
-    if !journal_dir.exists() {
-        fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
+    for (_, content) in read_notebook_files(notebook.as_deref())? {
+        for tag in parse_entry_tags(&content) {
+            tags.entry(tag.to_lowercase()).or_insert(tag);
+        }
     }
 
-    let file_path = journal_dir.join(&filename);
-
-    // Create file with frontmatter header
-    let initial_content = format!(
-        "---\ntitle: \ndate: {}\n---\n\n",
-        date_string
-    );
-    fs::write(&file_path, initial_content).map_err(|e| e.to_string())?;
+    Ok(tags.into_values().collect())
+}
 
-    Ok(filename)
+#[derive(Serialize)]
+struct TagCount {
+    /// Lowercased tag, the same for every spelling
+    tag: String,
+    /// Spelling to show, from the first entry (by filename) carrying the tag
+    display: String,
+    /// Number of entries carrying the tag
+    count: usize,
 }
 
+/// How many entries carry each tag, most used first, for a tag cloud. Tags that
+/// differ only by case are counted together.
 #[tauri::command]
-fn update_entry_metadata(filename: String, title: String, date: String, content: String) -> Result<String, String> {
-    let journal_dir = get_journal_dir();
+fn tag_counts(notebook: Option<String>) -> Result<Vec<TagCount>, String> {
+    let mut counts: BTreeMap<String, TagCount> = BTreeMap::new();
 
-    // Determine new filename based on title or date
-    let new_filename = if title.trim().is_empty() {
-        format!("{}.md", date)
-    } else {
-        format!("{}.md", title.trim())
-    };
+    for (_, content) in read_notebook_files(notebook.as_deref())? {
+        // An entry tagged both "Work" and "work" still counts once
+        let mut seen = HashSet::new();
+        for tag in parse_entry_tags(&content) {
+            let key = tag.to_lowercase();
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            counts
+                .entry(key.clone())
+                .or_insert_with(|| TagCount {
+                    tag: key,
+                    display: tag,
+                    count: 0,
+                })
+                .count += 1;
+        }
+    }
 
-    let old_path = journal_dir.join(&filename);
-    let new_path = journal_dir.join(&new_filename);
+    let mut counts: Vec<TagCount> = counts.into_values().collect();
+    // Stable, so equal counts stay in the map's alphabetical order
+    counts.sort_by(|a, b| b.count.cmp(&a.count));
+    Ok(counts)
+}
 
-    // Check if old file exists
-    if !old_path.exists() {
-        return Err("File does not exist".to_string());
+/// Replace the tag old (matched case-insensitively) with new in every entry's
+/// frontmatter, dropping the result's duplicates. Bodies and other fields are kept.
+/// Returns how many entries changed.
+#[tauri::command]
+fn rename_tag(old: String, new: String, notebook: Option<String>) -> Result<usize, String> {
+    let old = old.trim().to_lowercase();
+    let new = new.trim();
+    if old.is_empty() || new.is_empty() {
+        return Err("Tag names cannot be empty".to_string());
     }
 
-    // Check if new filename already exists (and it's not the same file)
-    if new_path.exists() && filename != new_filename {
-        return Err("A file with that name already exists".to_string());
-    }
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    let mut changed = 0;
 
-    // Update file content with new frontmatter
-    let updated_content = format!(
-        "---\ntitle: {}\ndate: {}\n---\n\n{}",
-        title,
-        date,
-        content
-    );
-    fs::write(&old_path, &updated_content).map_err(|e| e.to_string())?;
+    for (filename, content) in read_notebook_files(notebook.as_deref())? {
+        let tags = parse_entry_tags(&content);
+        if !tags.iter().any(|t| t.to_lowercase() == old) {
+            continue;
+        }
 
-    // Rename file if needed
-    if filename != new_filename {
-        fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
+        let mut seen = HashSet::new();
+        let renamed: Vec<serde_yaml::Value> = tags
+            .into_iter()
+            .map(|t| {
+                if t.to_lowercase() == old {
+                    new.to_string()
+                } else {
+                    t
+                }
+            })
+            .filter(|t| seen.insert(t.to_lowercase()))
+            .map(serde_yaml::Value::String)
+            .collect();
+
+        let updated =
+            frontmatter::set_value(&content, "tags", serde_yaml::Value::Sequence(renamed))
+                .map_err(|e| format!("{}: {}", filename, e))?;
+        write_atomic(&journal_dir.join(&filename), &updated)?;
+        changed += 1;
     }
 
-    Ok(new_filename)
+    Ok(changed)
 }
 
 #[tauri::command]
-fn rename_entry(old_filename: String, new_filename: String) -> Result<(), String> {
-    let journal_dir = get_journal_dir();
+fn read_entry(filename: String, notebook: Option<String>) -> Result<String, String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    let file_path = journal_dir.join(&filename);
 
-    // Ensure new filename ends with .md
-    let new_filename = if new_filename.ends_with(".md") {
-        new_filename
-    } else {
-        format!("{}.md", new_filename)
-    };
+    fs::read_to_string(&file_path).map_err(|e| e.to_string())
+}
 
-    let old_path = journal_dir.join(&old_filename);
-    let new_path = journal_dir.join(&new_filename);
+/// Read only the frontmatter block of an entry, without the body
+#[tauri::command]
+fn read_frontmatter(
+    filename: String,
+    notebook: Option<String>,
+) -> Result<frontmatter::Frontmatter, String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    let file = fs::File::open(journal_dir.join(&filename)).map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(file).lines();
 
-    // Check if old file exists
-    if !old_path.exists() {
-        return Err("File does not exist".to_string());
+    // No opening delimiter means no frontmatter
+    match lines.next() {
+        Some(Ok(line)) if line.trim_end() == "---" => {}
+        _ => return Ok(frontmatter::Frontmatter::new()),
     }
 
-    // Check if new filename already exists
-    if new_path.exists() {
-        return Err("A file with that name already exists".to_string());
+    let mut block = Vec::new();
+    for line in lines {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim_end() == "---" {
+            break;
+        }
+        block.push(line);
     }
 
-    fs::rename(&old_path, &new_path).map_err(|e| e.to_string())
+    Ok(frontmatter::parse_block(&block.join("\n")))
 }
 
 #[tauri::command]
-fn delete_entry(filename: String) -> Result<(), String> {
-    let journal_dir = get_journal_dir();
-    let file_path = journal_dir.join(&filename);
+fn save_entry(filename: String, content: String, notebook: Option<String>) -> Result<(), String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
 
-    // Check if file exists
-    if !file_path.exists() {
-        return Err("File does not exist".to_string());
+    // Create directory if it doesn't exist
+    if !journal_dir.exists() {
+        fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
     }
 
-    fs::remove_file(&file_path).map_err(|e| e.to_string())
+    let file_path = journal_dir.join(&filename);
+    history::snapshot_before_save(
+        &file_path,
+        &entry_key(notebook.as_deref(), &filename),
+        &content,
+    )?;
+    write_atomic(&file_path, &content)
 }
 
-// ============================================================================
-// Voice Recording & Transcription Commands
-// ============================================================================
+/// Modification time of a file in milliseconds since the Unix epoch
+fn file_mtime_ms(path: &Path) -> Result<u64, String> {
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| e.to_string())?;
+
+    Ok(modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0))
+}
 
+/// Modification time the frontend should hand back to save_entry_checked
 #[tauri::command]
-fn start_recording(state: State<RecorderState>) -> Result<(), String> {
-    let mut handle_guard = state.handle.lock().map_err(|e| format!("Lock error: {}", e))?;
+fn get_entry_mtime(filename: String, notebook: Option<String>) -> Result<u64, String> {
+    file_mtime_ms(&notebook_dir(notebook.as_deref())?.join(&filename))
+}
 
-    if handle_guard.is_some() {
-        return Err("Already recording".to_string());
-    }
+/// Save only if the file hasn't changed on disk since it was loaded at base_mtime.
+/// Returns "conflict: file changed on disk" instead of overwriting an external edit.
+/// Returns the new modification time for the next save.
+#[tauri::command]
+fn save_entry_checked(
+    filename: String,
+    content: String,
+    base_mtime: u64,
+    notebook: Option<String>,
+) -> Result<u64, String> {
+    let file_path = notebook_dir(notebook.as_deref())?.join(&filename);
 
-    // Start recording in a background thread
-    let handle = audio_recorder::start_recording_thread(state.shared.clone())?;
-    *handle_guard = Some(handle);
+    if file_path.exists() && file_mtime_ms(&file_path)? != base_mtime {
+        return Err("conflict: file changed on disk".to_string());
+    }
 
-    Ok(())
+    history::snapshot_before_save(
+        &file_path,
+        &entry_key(notebook.as_deref(), &filename),
+        &content,
+    )?;
+    write_atomic(&file_path, &content)?;
+    file_mtime_ms(&file_path)
 }
 
 #[tauri::command]
-fn stop_recording_and_transcribe(state: State<RecorderState>) -> Result<String, String> {
-    // Signal to stop recording
-    state.shared.stop_recording();
+fn create_entry(notebook: Option<String>) -> Result<String, String> {
+    let now = Local::now();
+    let date_string = now.format(&entry_date_format()).to_string();
 
-    // Wait for the recording thread to finish
-    let mut handle_guard = state.handle.lock().map_err(|e| format!("Lock error: {}", e))?;
-    if let Some(handle) = handle_guard.take() {
-        handle.join().map_err(|_| "Recording thread panicked")?;
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+
+    // Create directory if it doesn't exist
+    if !journal_dir.exists() {
+        fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
     }
 
-    // Get the recorded samples
-    let samples = state.shared.get_samples();
+    // A second entry on the same day gets " (2)" etc. rather than replacing the first
+    let filename = unique_filename(&journal_dir, &date_string);
+    let file_path = journal_dir.join(&filename);
 
-    if samples.is_empty() {
-        return Err("No audio was recorded".to_string());
-    }
+    // Start from the notebook's default template, or a bare frontmatter header
+    let initial_content = match templates::default_template_for(notebook.as_deref()) {
+        Some(template) => templates::render_template(&template, &now)?,
+        None => format!("---\ntitle: \ndate: {}\n---\n\n", date_string),
+    };
+    write_atomic(&file_path, &initial_content)?;
 
-    // Transcribe the audio
-    transcription::transcribe_audio(&samples)
+    Ok(filename)
 }
 
+/// Copy an entry as a new entry dated today, keeping its title and body.
+/// Returns the new filename.
 #[tauri::command]
-fn transcribe_audio_file(path: String) -> Result<String, String> {
-    let path = std::path::Path::new(&path);
+fn duplicate_entry(filename: String, notebook: Option<String>) -> Result<String, String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    let source_path = journal_dir.join(&filename);
 
-    if !path.exists() {
-        return Err("Audio file not found".to_string());
+    if !source_path.is_file() {
+        return Err("File does not exist".to_string());
     }
 
-    // Convert audio to Whisper format
-    let samples = audio_import::convert_to_whisper_format(path)?;
+    let content = fs::read_to_string(&source_path).map_err(|e| e.to_string())?;
+    let date_string = Local::now().format(&entry_date_format()).to_string();
 
-    // Transcribe
-    transcription::transcribe_audio(&samples)
-}
+    let new_filename = unique_filename(&journal_dir, &date_string);
+    let new_content = frontmatter::set_field(&content, "date", &date_string)?;
+    write_atomic(&journal_dir.join(&new_filename), &new_content)?;
+
+    Ok(new_filename)
+}
+
+// Longest filename stem we generate, in characters
+const MAX_FILENAME_CHARS: usize = 200;
+
+/// Turn a title into a safe filename stem: path separators, characters Windows
+/// rejects and control characters become spaces, whitespace is collapsed, leading
+/// and trailing dots are dropped and the result is truncated
+fn sanitize_filename(title: &str) -> String {
+    let replaced: String = title
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let truncated: String = collapsed
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .chars()
+        .take(MAX_FILENAME_CHARS)
+        .collect();
+
+    truncated
+        .trim_end_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string()
+}
+
+fn is_case_only_change(old_filename: &str, new_filename: &str) -> bool {
+    old_filename != new_filename && old_filename.to_lowercase() == new_filename.to_lowercase()
+}
+
+/// Whether renaming old_filename to new_filename would collide with another file.
+/// On case-insensitive filesystems a case-only change resolves to the same file,
+/// so it only counts as taken if a file with that exact name is listed.
+fn name_taken(dir: &std::path::Path, old_filename: &str, new_filename: &str) -> bool {
+    if !dir.join(new_filename).exists() {
+        return false;
+    }
+
+    if !is_case_only_change(old_filename, new_filename) {
+        return true;
+    }
+
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.file_name().to_str() == Some(new_filename))
+        })
+        .unwrap_or(true)
+}
+
+/// Rename a file, going through a temporary name for case-only changes so
+/// case-insensitive filesystems actually apply the new capitalization
+fn rename_path(
+    old_path: &std::path::Path,
+    new_path: &std::path::Path,
+    old_filename: &str,
+    new_filename: &str,
+) -> Result<(), String> {
+    if !is_case_only_change(old_filename, new_filename) {
+        return fs::rename(old_path, new_path).map_err(|e| e.to_string());
+    }
+
+    let temp_path = old_path.with_file_name(format!(".{}.renaming", old_filename));
+    fs::rename(old_path, &temp_path).map_err(|e| e.to_string())?;
+
+    if let Err(e) = fs::rename(&temp_path, new_path) {
+        // Put the original back rather than leaving the entry under the temporary name
+        fs::rename(&temp_path, old_path).ok();
+        return Err(e.to_string());
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+fn update_entry_metadata(
+    filename: String,
+    title: String,
+    date: String,
+    content: String,
+    mood: Option<String>,
+    location: Option<String>,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+
+    // An entry saved without a date is dated today
+    let date = if date.trim().is_empty() {
+        Local::now().format(&entry_date_format()).to_string()
+    } else {
+        date
+    };
+
+    // Determine new filename based on title, falling back to the date
+    let stem = match sanitize_filename(&title) {
+        stem if stem.is_empty() => sanitize_filename(&date),
+        stem => stem,
+    };
+    let new_filename = if stem.is_empty() {
+        filename.clone()
+    } else {
+        format!("{}.md", stem)
+    };
+
+    let old_path = journal_dir.join(&filename);
+    let new_path = journal_dir.join(&new_filename);
+
+    // Check if old file exists
+    if !old_path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    // Check if new filename already exists (and it's not the same file)
+    if filename != new_filename && name_taken(&journal_dir, &filename, &new_filename) {
+        return Err("A file with that name already exists".to_string());
+    }
+
+    // Patch the new title and date into the existing frontmatter so other fields survive
+    let existing = fs::read_to_string(&old_path).map_err(|e| e.to_string())?;
+    let mut updated_content = frontmatter::with_body(&existing, &format!("\n{}", content));
+    updated_content = frontmatter::set_field(&updated_content, "title", &title)?;
+    updated_content = frontmatter::set_field(&updated_content, "date", &date)?;
+
+    // Optional fields: None leaves them alone, an empty value removes them
+    for (key, value) in [("mood", mood), ("location", location)] {
+        match value.map(|v| v.trim().to_string()) {
+            Some(v) if v.is_empty() => {
+                updated_content = frontmatter::remove_field(&updated_content, key)?;
+            }
+            Some(v) => {
+                updated_content = frontmatter::set_field(&updated_content, key, &v)?;
+            }
+            None => {}
+        }
+    }
+
+    let notebook = notebook.as_deref();
+    history::snapshot_before_save(&old_path, &entry_key(notebook, &filename), &updated_content)?;
+    write_atomic(&old_path, &updated_content)?;
+
+    // Rename after the content is safely in place, so a failed rename leaves a single
+    // up-to-date file under the old name rather than two copies
+    if filename != new_filename {
+        rename_path(&old_path, &new_path, &filename, &new_filename)?;
+        history::rename_snapshot(
+            &entry_key(notebook, &filename),
+            &entry_key(notebook, &new_filename),
+        );
+        entry_cache::invalidate(&new_path);
+    }
+
+    Ok(new_filename)
+}
+
+/// Change only the frontmatter date of an entry, keeping its filename and everything else
+#[tauri::command]
+fn set_entry_date(filename: String, date: String, notebook: Option<String>) -> Result<(), String> {
+    let date = date.trim();
+    if parse_entry_date(date).is_none() {
+        return Err("Unrecognized date format".to_string());
+    }
+
+    let file_path = notebook_dir(notebook.as_deref())?.join(&filename);
+    let content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+
+    write_atomic(&file_path, &frontmatter::set_field(&content, "date", date)?)
+}
+
+/// Pin or unpin an entry so it lists above the rest
+#[tauri::command]
+fn set_pinned(filename: String, pinned: bool, notebook: Option<String>) -> Result<(), String> {
+    let file_path = notebook_dir(notebook.as_deref())?.join(&filename);
+    let content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+
+    write_atomic(
+        &file_path,
+        &frontmatter::set_value(&content, "pinned", serde_yaml::Value::Bool(pinned))?,
+    )
+}
+
+/// Record the spoken (audio) and written (text) languages of an entry.
+/// Fields passed as None are left as they are.
+#[tauri::command]
+fn set_entry_languages(
+    filename: String,
+    spoken_language: Option<String>,
+    language: Option<String>,
+    notebook: Option<String>,
+) -> Result<(), String> {
+    let file_path = notebook_dir(notebook.as_deref())?.join(&filename);
+    let mut content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+
+    if let Some(spoken_language) = spoken_language {
+        content = frontmatter::set_field(&content, "spoken_language", spoken_language.trim())?;
+    }
+    if let Some(language) = language {
+        content = frontmatter::set_field(&content, "language", language.trim())?;
+    }
+
+    write_atomic(&file_path, &content)
+}
+
+/// Value stored in an entry's `transcribed_with` field for the active model
+fn transcribed_with_value() -> String {
+    format!("whisper/{}", whisper_model::get_active_model())
+}
+
+/// Languages of a transcript: the one spoken in the audio and the one the text is in
+struct TranscriptLanguages<'a> {
+    spoken: &'a str,
+    text: &'a str,
+}
+
+// Transcription without translation or a chosen language is in English
+const ENGLISH: TranscriptLanguages = TranscriptLanguages {
+    spoken: "en",
+    text: "en",
+};
+
+/// Stamp an entry with the model a transcript written into it came from and, when
+/// known, its languages. `spoken_language` is only kept when it differs from the
+/// text's `language`, i.e. for translations, and removed when a later transcript isn't one.
+fn stamp_transcription(
+    filename: &str,
+    notebook: Option<&str>,
+    languages: Option<&TranscriptLanguages>,
+) -> Result<(), String> {
+    let file_path = notebook_dir(notebook)?.join(filename);
+    let mut content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+
+    content = frontmatter::set_field(&content, "transcribed_with", &transcribed_with_value())?;
+    if let Some(languages) = languages {
+        content = frontmatter::set_field(&content, "language", languages.text)?;
+        content = if languages.spoken != languages.text {
+            frontmatter::set_field(&content, "spoken_language", languages.spoken)?
+        } else {
+            frontmatter::remove_field(&content, "spoken_language")?
+        };
+    }
+
+    write_atomic(&file_path, &content)
+}
+
+/// Stamp the entry a transcript is going into, when the caller named one
+fn stamp_target_entry(
+    filename: Option<&str>,
+    notebook: Option<&str>,
+    languages: &TranscriptLanguages,
+) -> Result<(), String> {
+    match filename.map(str::trim).filter(|f| !f.is_empty()) {
+        Some(filename) => stamp_transcription(filename, notebook, Some(languages)),
+        None => Ok(()),
+    }
+}
+
+/// Stamp an entry with the model its transcript came from
+#[tauri::command]
+fn mark_entry_transcribed(filename: String, notebook: Option<String>) -> Result<(), String> {
+    stamp_transcription(&filename, notebook.as_deref(), None)
+}
+
+/// Model recorded in an entry's `transcribed_with` field, without the engine prefix
+fn entry_transcription_model(content: &str) -> Option<String> {
+    let value = frontmatter::get_str(&frontmatter::parse(content), "transcribed_with")?;
+    let model = value.rsplit('/').next().unwrap_or(&value).trim();
+
+    if model.is_empty() {
+        None
+    } else {
+        Some(model.to_string())
+    }
+}
+
+/// Entries transcribed with the given model, e.g. "tiny.en"
+#[tauri::command]
+fn list_entries_by_model(model: String) -> Result<Vec<String>, String> {
+    let model = model.trim().to_lowercase();
+
+    let mut filenames: Vec<String> = read_journal_files()?
+        .into_iter()
+        .filter(|(_, content)| {
+            entry_transcription_model(content).is_some_and(|m| m.to_lowercase() == model)
+        })
+        .map(|(filename, _)| filename)
+        .collect();
+
+    filenames.sort();
+    Ok(filenames)
+}
+
+/// Number of transcribed entries per model, most used first
+#[tauri::command]
+fn count_entries_by_model() -> Result<Vec<(String, usize)>, String> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    for (_, content) in read_journal_files()? {
+        if let Some(model) = entry_transcription_model(&content) {
+            *counts.entry(model).or_insert(0) += 1;
+        }
+    }
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(counts)
+}
+
+#[tauri::command]
+fn rename_entry(
+    old_filename: String,
+    new_filename: String,
+    notebook: Option<String>,
+) -> Result<(), String> {
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+
+    let old_path = journal_dir.join(&old_filename);
+
+    // Check if old file exists
+    if !old_path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    // A name that sanitizes to nothing falls back to the entry's date
+    let mut stem = sanitize_filename(new_filename.trim().trim_end_matches(".md"));
+    if stem.is_empty() {
+        let content = fs::read_to_string(&old_path).map_err(|e| e.to_string())?;
+        stem = sanitize_filename(&parse_frontmatter(&content).1);
+    }
+    if stem.is_empty() {
+        return Err("Invalid filename".to_string());
+    }
+
+    let new_filename = format!("{}.md", stem);
+    let new_path = journal_dir.join(&new_filename);
+
+    // Check if new filename already exists
+    if name_taken(&journal_dir, &old_filename, &new_filename) {
+        return Err("A file with that name already exists".to_string());
+    }
+
+    rename_path(&old_path, &new_path, &old_filename, &new_filename)?;
+    history::rename_snapshot(
+        &entry_key(notebook.as_deref(), &old_filename),
+        &entry_key(notebook.as_deref(), &new_filename),
+    );
+    entry_cache::invalidate(&old_path);
+    entry_cache::invalidate(&new_path);
+
+    Ok(())
+}
+
+/// Move an entry in a notebook (or the top-level journal) to the trash
+fn trash_entry(notebook: Option<&str>, filename: &str) -> Result<(), String> {
+    let file_path = notebook_dir(notebook)?.join(filename);
+
+    // Check if file exists
+    if !file_path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    // Entries go to the trash rather than being removed outright
+    trash::move_to_trash(&file_path, filename, notebook)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_entry(filename: String, notebook: Option<String>) -> Result<(), String> {
+    trash_entry(notebook.as_deref(), &filename)
+}
+
+#[derive(Serialize)]
+struct BulkResult {
+    deleted: Vec<String>,
+    /// (filename, reason) for each entry that couldn't be deleted
+    failed: Vec<(String, String)>,
+}
+
+/// Move several entries to the trash. A failure is recorded and the rest still go.
+#[tauri::command]
+fn delete_entries(filenames: Vec<String>, notebook: Option<String>) -> Result<BulkResult, String> {
+    // An invalid notebook fails the whole call rather than every file in it
+    notebook_dir(notebook.as_deref())?;
+    let mut result = BulkResult {
+        deleted: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for filename in filenames {
+        match trash_entry(notebook.as_deref(), &filename) {
+            Ok(()) => result.deleted.push(filename),
+            Err(e) => result.failed.push((filename, e)),
+        }
+    }
+
+    Ok(result)
+}
+
+// ============================================================================
+// Voice Recording & Transcription Commands
+// ============================================================================
+
+fn begin_recording(
+    state: &RecorderState,
+    options: audio_recorder::RecordingOptions,
+) -> Result<(), String> {
+    let mut handle_guard = state
+        .handle
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+
+    if handle_guard.is_some() {
+        return Err("Already recording".to_string());
+    }
+
+    // Start recording in a background thread
+    let handle = audio_recorder::start_recording_thread(state.shared.clone(), options)?;
+    *handle_guard = Some(handle);
+
+    Ok(())
+}
+
+#[tauri::command]
+fn start_recording(
+    state: State<RecorderState>,
+    window: Window,
+    device: Option<String>,
+    max_duration_secs: Option<u32>,
+    silence_rms: Option<f32>,
+    silence_timeout_secs: Option<f32>,
+) -> Result<(), String> {
+    begin_recording(
+        &state,
+        audio_recorder::RecordingOptions {
+            window: Some(window),
+            device_name: device,
+            max_duration_secs,
+            silence_rms,
+            silence_timeout_secs,
+        },
+    )
+}
+
+/// Record for a fixed number of seconds: a recording with max_duration_secs set, so it
+/// emits `recording-countdown` each second and `recording-auto-stopped` when time
+/// runs out. The regular stop command still ends it early and collects the transcript
+/// either way.
+#[tauri::command]
+fn record_for_seconds(
+    state: State<RecorderState>,
+    window: Window,
+    secs: u32,
+    device: Option<String>,
+) -> Result<(), String> {
+    if secs == 0 {
+        return Err("Duration must be at least one second".to_string());
+    }
+
+    start_recording(state, window, device, Some(secs), None, None)
+}
+
+/// Seconds of audio captured by the current (or most recent) recording
+#[tauri::command]
+fn get_recording_elapsed(state: State<RecorderState>) -> f32 {
+    state.shared.elapsed_secs(state.shared.sample_rate())
+}
+
+/// Stop recording and transcribe it. When filename names the entry the transcript
+/// is going into, the entry is stamped with the model used.
+#[tauri::command]
+fn stop_recording_and_transcribe(
+    state: State<RecorderState>,
+    filename: Option<String>,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    // Signal to stop recording
+    state.shared.stop_recording();
+
+    // Wait for the recording thread to finish
+    let mut handle_guard = state
+        .handle
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(handle) = handle_guard.take() {
+        handle.join().map_err(|_| "Recording thread panicked")?;
+    }
+
+    // Get the recorded samples
+    let samples = state.shared.get_samples();
+
+    if samples.is_empty() {
+        return Err("No audio was recorded".to_string());
+    }
+
+    // Transcribe the audio
+    let text = transcription::transcribe_audio(&samples)?;
+    stamp_target_entry(filename.as_deref(), notebook.as_deref(), &ENGLISH)?;
+    Ok(text)
+}
+
+/// Transcribe what has been captured so far without interrupting the recording.
+/// Works on a copy, so the recording thread only waits for the snapshot.
+#[tauri::command]
+fn transcribe_current_buffer(state: State<RecorderState>) -> Result<String, String> {
+    let (samples, rate) = state.shared.snapshot_from(0);
+
+    if samples.is_empty() {
+        return Err("No audio was recorded".to_string());
+    }
+
+    let samples = audio_recorder::resample(&samples, rate, 16000);
+    transcription::transcribe_audio(&samples)
+}
+
+/// Record a short sample, measure peak and RMS, and suggest a gain factor.
+/// The calibration audio is discarded.
+#[tauri::command]
+async fn calibrate_input(
+    state: State<'_, RecorderState>,
+    duration_secs: f32,
+) -> Result<audio_analysis::InputCalibration, String> {
+    if !(0.5..=30.0).contains(&duration_secs) {
+        return Err("Calibration duration must be between 0.5 and 30 seconds".to_string());
+    }
+
+    if state.shared.is_recording() {
+        return Err("Already recording".to_string());
+    }
+
+    let duration = std::time::Duration::from_secs_f32(duration_secs);
+    let samples =
+        tauri::async_runtime::spawn_blocking(move || audio_recorder::record_for(duration))
+            .await
+            .map_err(|e| format!("Calibration failed: {}", e))??;
+
+    if samples.is_empty() {
+        return Err("No audio was recorded".to_string());
+    }
+
+    Ok(audio_analysis::calibrate_levels(&samples))
+}
+
+/// Record straight into an entry, transcribing and appending every chunk_secs
+/// seconds so the entry grows live and survives a crash mid-session
+#[tauri::command]
+fn start_live_dictation(
+    state: State<RecorderState>,
+    window: Window,
+    filename: String,
+    chunk_secs: Option<u32>,
+    notebook: Option<String>,
+) -> Result<(), String> {
+    let entry_path = notebook_dir(notebook.as_deref())?.join(&filename);
+    if !entry_path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    let chunk_secs = chunk_secs.unwrap_or(30).max(5);
+
+    stamp_transcription(&filename, notebook.as_deref(), Some(&ENGLISH))?;
+    begin_recording(
+        &state,
+        audio_recorder::RecordingOptions {
+            window: Some(window.clone()),
+            ..Default::default()
+        },
+    )?;
+
+    let worker =
+        dictation::spawn_live_dictation(state.shared.clone(), entry_path, chunk_secs, window);
+    *state
+        .dictation
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))? = Some(worker);
+
+    Ok(())
+}
+
+/// Stop live dictation, waiting for the final chunk to be appended
+#[tauri::command]
+fn stop_live_dictation(state: State<RecorderState>) -> Result<(), String> {
+    state.shared.stop_recording();
+
+    let mut handle_guard = state
+        .handle
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(handle) = handle_guard.take() {
+        handle.join().map_err(|_| "Recording thread panicked")?;
+    }
+
+    let mut dictation_guard = state
+        .dictation
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(worker) = dictation_guard.take() {
+        worker.join().map_err(|_| "Dictation thread panicked")?;
+    }
+
+    Ok(())
+}
+
+/// Transcribe several audio files one after another, emitting `batch-progress` events.
+/// A file that fails is reported in its result instead of stopping the batch.
+#[tauri::command]
+fn import_and_transcribe_batch(
+    window: Window,
+    paths: Vec<String>,
+) -> Result<Vec<BatchResult>, String> {
+    Ok(transcribe_batch(
+        &paths,
+        |path| {
+            audio_import::convert_to_whisper_format(path)
+                .and_then(|samples| transcription::transcribe_audio(&samples))
+        },
+        |progress| {
+            let _ = window.emit("batch-progress", progress);
+        },
+    ))
+}
+
+/// Run transcribe over each path in turn, collecting a result per file and
+/// reporting progress before and after each one
+fn transcribe_batch<T, E>(paths: &[String], mut transcribe: T, mut emit: E) -> Vec<BatchResult>
+where
+    T: FnMut(&Path) -> Result<String, String>,
+    E: FnMut(BatchProgress),
+{
+    let total = paths.len();
+    let mut results = Vec::with_capacity(total);
+
+    // Sequential on purpose: every file goes through the one shared Whisper context
+    for (index, path) in paths.iter().enumerate() {
+        let path = Path::new(path);
+        let filename = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let progress = |status| BatchProgress {
+            index,
+            total,
+            filename: filename.clone(),
+            status,
+        };
+        emit(progress("transcribing"));
+
+        let transcript = if path.exists() {
+            transcribe(path)
+        } else {
+            Err("Audio file not found".to_string())
+        };
+
+        emit(progress(if transcript.is_ok() { "done" } else { "failed" }));
+
+        results.push(match transcript {
+            Ok(text) => BatchResult {
+                filename,
+                transcript: Some(text),
+                error: None,
+            },
+            Err(e) => BatchResult {
+                filename,
+                transcript: None,
+                error: Some(e),
+            },
+        });
+    }
+
+    results
+}
+
+/// Decode an audio file and report whether it holds meaningful audio, without transcribing
+#[tauri::command]
+fn probe_import(window: Window, path: String) -> Result<ImportProbe, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format_with_progress(path, &window)?;
+
+    Ok(ImportProbe {
+        duration_secs: audio_import::get_audio_duration(&samples, 16000),
+        peak: audio_analysis::peak_amplitude(&samples),
+        rms: audio_analysis::rms(&samples),
+        has_audio: audio_analysis::has_meaningful_audio(&samples),
+    })
+}
+
+/// Waveform peaks of an audio file at `from` Hz and after resampling it to `to` Hz,
+/// for overlaying in the UI to check the resampler preserves the signal
+#[tauri::command]
+fn resample_preview(
+    path: String,
+    from: u32,
+    to: u32,
+    buckets: Option<usize>,
+) -> Result<ResamplePreview, String> {
+    if from == 0 || to == 0 {
+        return Err("Sample rates must be greater than zero".to_string());
+    }
+
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let (samples, native_rate) = audio_import::decode_mono(path)?;
+    let original = audio_recorder::resample(&samples, native_rate, from);
+    let resampled = audio_recorder::resample(&original, from, to);
+
+    let buckets = buckets.unwrap_or(1000);
+
+    Ok(ResamplePreview {
+        from_rate: from,
+        to_rate: to,
+        original_peaks: audio_analysis::waveform_peaks(&original, buckets),
+        resampled_peaks: audio_analysis::waveform_peaks(&resampled, buckets),
+    })
+}
+
+/// Measure clipping in an audio file so the user can decide whether to de-clip
+#[tauri::command]
+fn analyze_clipping(path: String) -> Result<audio_analysis::ClipStats, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    audio_import::convert_with_options(path, &audio_import::ImportOptions::default())
+        .map(|imported| imported.clip_stats)
+}
+
+#[tauri::command]
+fn transcribe_audio_file(
+    window: Window,
+    path: String,
+    declip: Option<bool>,
+    trim_silence: Option<bool>,
+    track_id: Option<u32>,
+    n_threads: Option<usize>,
+    initial_prompt: Option<String>,
+    strategy: Option<transcription::DecodingStrategy>,
+    filename: Option<String>,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    // Convert audio to Whisper format
+    let options = audio_import::ImportOptions {
+        declip: declip.unwrap_or(false),
+        trim_silence: trim_silence
+            .unwrap_or(false)
+            .then_some(audio_analysis::DEFAULT_SILENCE_RMS),
+        track_id,
+    };
+    let samples = audio_import::import_samples(path, &options, |percent| {
+        let _ = window.emit("import-progress", percent);
+    })?;
+
+    // Refuse files that decode to silence rather than producing an empty entry
+    if !audio_analysis::has_meaningful_audio(&samples) {
+        return Err("No meaningful audio found in file".to_string());
+    }
+
+    // Transcribe
+    let options = transcription::TranscribeOptions {
+        n_threads,
+        initial_prompt,
+        strategy: strategy.unwrap_or_default(),
+        ..Default::default()
+    };
+    let text = transcription::transcribe_text(&samples, &options)?;
+    stamp_target_entry(filename.as_deref(), notebook.as_deref(), &ENGLISH)?;
+    Ok(text)
+}
+
+/// Transcribe raw mono samples at an arbitrary rate. Rates or durations that look
+/// implausible come back as warnings alongside the text.
+#[tauri::command]
+fn transcribe_samples(samples: Vec<f32>, sample_rate: u32) -> Result<SampleTranscription, String> {
+    if sample_rate == 0 {
+        return Err("Sample rate must be greater than zero".to_string());
+    }
+
+    if samples.is_empty() {
+        return Err("No audio samples provided".to_string());
+    }
+
+    let warnings = audio_analysis::sample_rate_warnings(samples.len(), sample_rate);
+    let samples = audio_recorder::resample(&samples, sample_rate, 16000);
+    let text = transcription::transcribe_audio(&samples)?;
+
+    Ok(SampleTranscription { text, warnings })
+}
+
+/// The requested spoken language, or the one detected in samples when none was given
+fn resolve_spoken_language(language: Option<String>, samples: &[f32]) -> Result<String, String> {
+    match language
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+    {
+        Some(language) => Ok(language),
+        None => transcription::detect_language(samples),
+    }
+}
+
+/// Transcribe an audio file in the given language ("es", "fr", ...), or detect the
+/// language when none is given. Needs a multilingual model for anything but English.
+/// When filename names the target entry, its model and language are recorded.
+#[tauri::command]
+fn transcribe_audio_file_in_language(
+    path: String,
+    language: Option<String>,
+    filename: Option<String>,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format(path)?;
+    let language = resolve_spoken_language(language, &samples)?;
+    let text = transcription::transcribe_audio_with_language(&samples, Some(&language))?;
+
+    let languages = TranscriptLanguages {
+        spoken: &language,
+        text: &language,
+    };
+    stamp_target_entry(filename.as_deref(), notebook.as_deref(), &languages)?;
+    Ok(text)
+}
+
+/// Transcribe an audio file as English text, translating from its spoken language
+/// (given, or detected when None). Needs a multilingual model. When filename names
+/// the target entry, its model and both languages are recorded.
+#[tauri::command]
+fn translate_audio_file(
+    path: String,
+    language: Option<String>,
+    filename: Option<String>,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format(path)?;
+    let language = resolve_spoken_language(language, &samples)?;
+    let text = transcription::translate_audio(&samples, Some(&language))?;
+
+    let languages = TranscriptLanguages {
+        spoken: &language,
+        text: "en",
+    };
+    stamp_target_entry(filename.as_deref(), notebook.as_deref(), &languages)?;
+    Ok(text)
+}
+
+/// Transcribe an audio file, emitting `transcription-progress` events (0-100) as it goes
+#[tauri::command]
+fn transcribe_with_progress(window: Window, path: String) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format_with_progress(path, &window)?;
+
+    let options = transcription::TranscribeOptions {
+        on_progress: Some(Arc::new(move |percent| {
+            let _ = window.emit("transcription-progress", percent);
+        })),
+        ..Default::default()
+    };
+    transcription::transcribe_text(&samples, &options)
+}
+
+/// Transcribe a long audio file in chunks, emitting `transcription-progress` and
+/// `transcription-partial` after each
+#[tauri::command]
+fn transcribe_long_file(window: Window, path: String) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format_with_progress(path, &window)?;
+    long_transcription::transcribe_long(&samples, &window)
+}
+
+/// Spoken language of an audio file as a code like "es"; needs a multilingual model
+#[tauri::command]
+fn detect_audio_language(path: String) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format(path)?;
+    transcription::detect_language(&samples)
+}
+
+/// Transcribe an audio file into timed segments. Segments with a confidence below
+/// min_confidence (0-1) come back marked low_confidence.
+#[tauri::command]
+fn transcribe_audio_file_detailed(
+    path: String,
+    min_confidence: Option<f32>,
+) -> Result<Vec<transcription::Segment>, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format(path)?;
+    let mut segments = transcription::transcribe_audio_detailed(&samples)?;
+
+    if let Some(min_confidence) = min_confidence {
+        transcription::mark_low_confidence(&mut segments, min_confidence);
+    }
+
+    Ok(segments)
+}
+
+/// Transcribe an audio file and return the per-segment data as versioned JSON
+#[tauri::command]
+fn export_transcription_json(
+    path: String,
+    out_path: Option<String>,
+    include_tokens: Option<bool>,
+) -> Result<String, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format(path)?;
+    let options = transcription::TranscribeOptions {
+        token_timestamps: include_tokens.unwrap_or(false),
+        ..Default::default()
+    };
+    let segments = transcription::transcribe_segments(&samples, &options)?;
+
+    transcript_export::segments_to_json(&segments, out_path.as_deref())
+}
+
+/// Save the most recent recording as a 16kHz mono 16-bit WAV file
+#[tauri::command]
+fn save_recording_wav(state: State<RecorderState>, path: String) -> Result<(), String> {
+    if state.shared.is_recording() {
+        return Err("Recording still in progress".to_string());
+    }
+
+    let (samples, sample_rate) = state.shared.snapshot_from(0);
+    if samples.is_empty() {
+        return Err("No audio was recorded".to_string());
+    }
+
+    let path = Path::new(&path);
+    export::ensure_parent_exists(path)?;
+    audio_recorder::write_wav(&samples, sample_rate, path)
+}
+
+/// Same as export_transcription_json, for the most recent recording
+#[tauri::command]
+fn export_recording_json(
+    state: State<RecorderState>,
+    out_path: Option<String>,
+    include_tokens: Option<bool>,
+) -> Result<String, String> {
+    if state.shared.is_recording() {
+        return Err("Recording still in progress".to_string());
+    }
+
+    let samples = state.shared.get_samples();
+    if samples.is_empty() {
+        return Err("No audio was recorded".to_string());
+    }
+
+    let options = transcription::TranscribeOptions {
+        token_timestamps: include_tokens.unwrap_or(false),
+        ..Default::default()
+    };
+    let segments = transcription::transcribe_segments(&samples, &options)?;
+
+    transcript_export::segments_to_json(&segments, out_path.as_deref())
+}
+
+#[tauri::command]
+fn recording_silence_ratio(state: State<RecorderState>, threshold: f32) -> Result<f32, String> {
+    let samples = state.shared.get_samples();
+
+    if samples.is_empty() {
+        return Err("No audio was recorded".to_string());
+    }
+
+    Ok(audio_analysis::silence_ratio(&samples, threshold))
+}
+
+#[tauri::command]
+fn audio_file_silence_ratio(path: String, threshold: f32) -> Result<f32, String> {
+    let path = std::path::Path::new(&path);
+
+    if !path.exists() {
+        return Err("Audio file not found".to_string());
+    }
+
+    let samples = audio_import::convert_to_whisper_format(path)?;
+    Ok(audio_analysis::silence_ratio(&samples, threshold))
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -305,26 +1836,711 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .setup(|app| {
+            // Sweep expired trash in the background so startup isn't delayed
+            std::thread::spawn(|| match trash::purge_trash_with_settings() {
+                Ok(0) => {}
+                Ok(purged) => println!("Purged {} expired item(s) from trash", purged),
+                Err(e) => eprintln!("Failed to purge trash: {}", e),
+            });
+
+            // Models used to live inside the journal; move any found there to the models dir
+            std::thread::spawn(|| match whisper_model::migrate_legacy_models() {
+                Ok(0) => {}
+                Ok(moved) => println!("Moved {} model file(s) out of the journal", moved),
+                Err(e) => eprintln!("Failed to migrate models: {}", e),
+            });
+
+            // Notice edits made outside the app; the journal still works without it
+            let journal_watcher = app.state::<watcher::JournalWatcher>();
+            if let Err(e) = watcher::start_watching(app.handle(), &journal_watcher) {
+                eprintln!("Failed to watch journal directory: {}", e);
+            }
+            Ok(())
+        })
+        .manage(watcher::JournalWatcher::default())
         .manage(RecorderState {
             shared: SharedSamples::new(),
             handle: Mutex::new(None),
+            dictation: Mutex::new(None),
         })
         .invoke_handler(tauri::generate_handler![
             // Journal commands
             list_entries,
+            list_entries_sorted,
+            list_large_entries,
+            list_entries_by_tag,
+            list_all_tags,
+            tag_counts,
+            rename_tag,
             read_entry,
+            read_frontmatter,
+            search::search_entries,
+            list_notebooks,
+            create_notebook,
+            set_journal_dir,
+            get_journal_dir_setting,
+            get_date_format,
+            set_date_format,
             save_entry,
+            save_entry_checked,
+            get_entry_mtime,
             create_entry,
+            duplicate_entry,
+            markdown_import::import_markdown,
             rename_entry,
             update_entry_metadata,
+            set_entry_date,
+            set_pinned,
+            set_entry_languages,
+            mark_entry_transcribed,
+            list_entries_by_model,
+            count_entries_by_model,
             delete_entry,
+            delete_entries,
+            digest::generate_weekly_digest,
+            export::export_entries_by_tag,
+            export::export_all_zip,
+            export::export_full_json,
+            export::export_entry_html,
+            pdf_export::export_entry_pdf,
+            history::undo_last_save,
+            links::list_link_counts,
+            links::list_orphan_entries,
+            links::get_backlinks,
+            maintenance::find_nonstandard_notes,
+            maintenance::normalize_extensions,
+            maintenance::normalize_dates,
+            stats::word_count_histogram,
+            stats::top_terms,
+            stats::entry_stats,
+            stats::journal_stats,
+            stats::writing_streaks,
+            stats::entries_per_day,
+            templates::get_notebook_template,
+            templates::set_notebook_template,
+            templates::list_templates,
+            templates::create_entry_from_template,
+            trash::get_trash_retention_days,
+            trash::set_trash_retention_days,
+            trash::purge_expired_trash_now,
+            trash::list_trash,
+            trash::restore_entry,
+            trash::purge_entry,
             // Voice commands
             whisper_model::check_whisper_model,
             whisper_model::download_whisper_model,
+            whisper_model::get_current_model,
+            whisper_model::switch_model,
+            whisper_model::list_models,
+            whisper_model::verify_model,
+            whisper_model::cancel_model_download,
+            whisper_model::delete_model,
+            whisper_model::models_disk_usage,
+            whisper_model::set_model_from_file,
+            whisper_model::set_custom_model_url,
+            whisper_model::get_models_dir_setting,
+            whisper_model::set_models_dir,
+            transcription::get_transcription_priority,
+            transcription::set_transcription_priority,
+            transcription::get_transcription_threads,
+            transcription::cancel_transcription,
+            transcription::gpu_available,
+            transcription::set_transcription_threads,
             start_recording,
+            audio_recorder::list_input_devices,
+            record_for_seconds,
+            get_recording_elapsed,
             stop_recording_and_transcribe,
-            transcribe_audio_file
+            transcribe_audio_file,
+            import_and_transcribe_batch,
+            transcribe_audio_file_detailed,
+            transcribe_audio_file_in_language,
+            translate_audio_file,
+            transcribe_with_progress,
+            transcribe_long_file,
+            detect_audio_language,
+            transcript_export::export_srt,
+            transcript_export::export_vtt,
+            probe_import,
+            audio_import::probe_audio_metadata,
+            audio_import::validate_audio_file,
+            audio_import::list_audio_tracks,
+            analyze_clipping,
+            resample_preview,
+            transcribe_samples,
+            export_transcription_json,
+            export_recording_json,
+            save_recording_wav,
+            transcribe_current_buffer,
+            calibrate_input,
+            start_live_dictation,
+            stop_live_dictation,
+            transliteration::transliterate_transcript,
+            recording_silence_ratio,
+            audio_file_silence_ratio
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<watcher::JournalWatcher>().stop();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TestJournal};
+
+    /// Filenames in a folder, sorted, skipping hidden files
+    fn listed(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .filter_map(|e| e.file_name().to_str().map(String::from))
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn case_only_change_is_detected() {
+        assert!(is_case_only_change("march.md", "March.md"));
+        assert!(!is_case_only_change("March.md", "March.md"));
+        assert!(!is_case_only_change("march.md", "April.md"));
+    }
+
+    #[test]
+    fn rename_changes_only_the_case() {
+        let journal = TestJournal::new();
+        journal.write("march notes.md", &entry("", "March 1, 2024", &[], "Body"));
+
+        rename_entry("march notes.md".into(), "March Notes".into(), None).unwrap();
+
+        assert_eq!(listed(&journal.root()), vec!["March Notes.md"]);
+        assert!(journal.read("March Notes.md").ends_with("Body"));
+    }
+
+    #[test]
+    fn rename_refuses_to_replace_another_entry() {
+        let journal = TestJournal::new();
+        journal.write("a.md", "first");
+        journal.write("b.md", "second");
+
+        let err = rename_entry("a.md".into(), "b".into(), None).unwrap_err();
+
+        assert_eq!(err, "A file with that name already exists");
+        assert_eq!(journal.read("b.md"), "second");
+    }
+
+    fn today() -> String {
+        Local::now().format(&entry_date_format()).to_string()
+    }
+
+    #[test]
+    fn duplicate_gets_a_new_name_and_todays_date() {
+        let journal = TestJournal::new();
+        let original = entry("Trip", "March 1, 2024", &["travel"], "Packed the bags.");
+        journal.write("Trip.md", &original);
+
+        let copy = duplicate_entry("Trip.md".into(), None).unwrap();
+
+        assert_ne!(copy, "Trip.md");
+        assert_eq!(journal.read("Trip.md"), original);
+
+        let content = journal.read(&copy);
+        let fields = frontmatter::parse(&content);
+        assert_eq!(frontmatter::get_str(&fields, "date"), Some(today()));
+        assert_eq!(
+            frontmatter::get_str(&fields, "title").as_deref(),
+            Some("Trip")
+        );
+        assert_eq!(parse_entry_tags(&content), ["travel"]);
+        assert!(content.ends_with("Packed the bags."));
+    }
+
+    #[test]
+    fn failed_temp_write_leaves_the_original_alone() {
+        let journal = TestJournal::new();
+        let path = journal.write("a.md", "original");
+        // A folder where the temp file would go makes writing it fail
+        fs::create_dir(journal.root().join(".a.md.tmp")).unwrap();
+
+        assert!(write_atomic(&path, "replacement").is_err());
+        assert_eq!(journal.read("a.md"), "original");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_directory_is_an_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let journal = TestJournal::new();
+        let path = journal.write("a.md", "original");
+        fs::set_permissions(journal.root(), fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Permissions don't bind root, so there is nothing to check there
+        let probe = journal.root().join("probe");
+        let writable = fs::write(&probe, "").is_ok();
+        fs::remove_file(&probe).ok();
+
+        if !writable {
+            assert!(save_entry("a.md".into(), "replacement".into(), None).is_err());
+            assert_eq!(journal.read("a.md"), "original");
+            assert!(!journal.root().join(".a.md.tmp").exists());
+        }
+
+        fs::set_permissions(journal.root(), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn save_checked_refuses_to_overwrite_an_external_edit() {
+        let journal = TestJournal::new();
+        let path = journal.write("a.md", "loaded");
+        let base_mtime = get_entry_mtime("a.md".into(), None).unwrap();
+
+        // Another program edits the file after the editor loaded it
+        fs::write(&path, "external").unwrap();
+        crate::test_support::set_mtime(
+            &path,
+            fs::metadata(&path).unwrap().modified().unwrap() + std::time::Duration::from_secs(5),
+        );
+
+        let err = save_entry_checked("a.md".into(), "mine".into(), base_mtime, None).unwrap_err();
+        assert_eq!(err, "conflict: file changed on disk");
+        assert_eq!(journal.read("a.md"), "external");
+
+        let current = get_entry_mtime("a.md".into(), None).unwrap();
+        let saved = save_entry_checked("a.md".into(), "mine".into(), current, None).unwrap();
+        assert_eq!(journal.read("a.md"), "mine");
+        assert_eq!(saved, get_entry_mtime("a.md".into(), None).unwrap());
+    }
+
+    #[test]
+    fn sanitize_filename_handles_unsafe_titles() {
+        assert_eq!(sanitize_filename("a/b\\c"), "a b c");
+        assert_eq!(sanitize_filename("Meeting: 10:30"), "Meeting 10 30");
+        assert_eq!(sanitize_filename("..hidden.."), "hidden");
+        assert_eq!(sanitize_filename("  . dots .  "), "dots");
+        assert_eq!(sanitize_filename("Tab\tand\nnewline"), "Tab and newline");
+        assert_eq!(sanitize_filename("..."), "");
+        assert_eq!(
+            sanitize_filename(&"x".repeat(250)).chars().count(),
+            MAX_FILENAME_CHARS
+        );
+        // Cutting at the limit can leave a trailing space, which is dropped too
+        assert_eq!(
+            sanitize_filename(&format!("{} b", "a".repeat(199))),
+            "a".repeat(199)
+        );
+    }
+
+    fn sorted_filenames(sort: SortSpec) -> Vec<String> {
+        list_entries_sorted(sort, None)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect()
+    }
+
+    #[test]
+    fn each_sort_order() {
+        let journal = TestJournal::new();
+        journal.write("a.md", &entry("banana", "March 3, 2024", &[], ""));
+        journal.write("b.md", &entry("Apple", "March 2, 2024", &[], ""));
+        journal.write("c.md", &entry("date", "", &[], ""));
+        journal.write("d.md", &entry("cherry", "someday", &[], ""));
+
+        // Undated entries stay together after the dated ones, or before them ascending
+        assert_eq!(
+            sorted_filenames(SortSpec::DateDesc),
+            ["a.md", "b.md", "d.md", "c.md"]
+        );
+        assert_eq!(
+            sorted_filenames(SortSpec::DateAsc),
+            ["c.md", "d.md", "b.md", "a.md"]
+        );
+        assert_eq!(
+            sorted_filenames(SortSpec::TitleAsc),
+            ["b.md", "a.md", "d.md", "c.md"]
+        );
+        assert_eq!(
+            sorted_filenames(SortSpec::TitleDesc),
+            ["c.md", "d.md", "a.md", "b.md"]
+        );
+        assert_eq!(
+            sorted_filenames(SortSpec::FilenameAsc),
+            ["a.md", "b.md", "c.md", "d.md"]
+        );
+    }
+
+    #[test]
+    fn pinned_older_entry_lists_first() {
+        let journal = TestJournal::new();
+        journal.write("new.md", &entry("New", "March 3, 2024", &[], ""));
+        journal.write("old.md", &entry("Old", "March 1, 2024", &[], ""));
+        set_pinned("old.md".into(), true, None).unwrap();
+
+        assert_eq!(sorted_filenames(SortSpec::DateDesc), ["old.md", "new.md"]);
+        assert!(journal.read("old.md").contains("pinned: true"));
+    }
+
+    #[test]
+    fn mood_without_location() {
+        let journal = TestJournal::new();
+        journal.write("a.md", &entry("", "March 1, 2024", &[], "Body"));
+
+        let filename = update_entry_metadata(
+            "a.md".into(),
+            "Walk".into(),
+            "March 1, 2024".into(),
+            "Body".into(),
+            Some("calm".into()),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(filename, "Walk.md");
+        assert!(!journal.read("Walk.md").contains("location"));
+
+        let entries = list_entries(None).unwrap();
+        assert_eq!(entries[0].mood.as_deref(), Some("calm"));
+        assert_eq!(entries[0].location, None);
+    }
+
+    #[test]
+    fn iso_date_format_names_and_sorts_entries() {
+        let journal = TestJournal::new();
+        set_date_format("%Y-%m-%d".into()).unwrap();
+
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(create_entry(None).unwrap(), format!("{}.md", today));
+
+        journal.write("2023-12-31.md", &entry("", "2023-12-31", &[], ""));
+        journal.write("2024-01-02.md", &entry("", "2024-01-02", &[], ""));
+        // Written before the format changed
+        journal.write("old.md", &entry("", "January 1, 2024", &[], ""));
+
+        assert_eq!(
+            sorted_filenames(SortSpec::DateDesc),
+            [
+                format!("{}.md", today).as_str(),
+                "2024-01-02.md",
+                "old.md",
+                "2023-12-31.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn date_format_must_round_trip_to_a_filename() {
+        let _journal = TestJournal::new();
+
+        assert!(set_date_format("%Y/%m/%d".into()).is_err());
+        assert!(set_date_format("%B %Y".into()).is_err());
+        assert_eq!(entry_date_format(), settings::DEFAULT_DATE_FORMAT);
+    }
+
+    fn notebook_filenames(notebook: &str) -> Vec<String> {
+        list_entries(Some(notebook.into()))
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect()
+    }
+
+    #[test]
+    fn notebooks_keep_their_entries_apart() {
+        let journal = TestJournal::new();
+        create_notebook("Work".into()).unwrap();
+        create_notebook("Home".into()).unwrap();
+        assert!(create_notebook("../outside".into()).is_err());
+
+        save_entry("a.md".into(), "work".into(), Some("Work".into())).unwrap();
+        save_entry("b.md".into(), "home".into(), Some("Home".into())).unwrap();
+
+        assert_eq!(list_notebooks().unwrap(), ["Home", "Work"]);
+        assert_eq!(notebook_filenames("Work"), ["a.md"]);
+        assert_eq!(notebook_filenames("Home"), ["b.md"]);
+        assert!(list_entries(None).unwrap().is_empty());
+        assert!(read_entry("a.md".into(), Some("Home".into())).is_err());
+        assert_eq!(journal.read("Work/a.md"), "work");
+    }
+
+    #[test]
+    fn metadata_and_tag_commands_stay_in_their_notebook() {
+        let journal = TestJournal::new();
+        let note = entry("Note", "March 1, 2024", &["idea"], "Body");
+        journal.write("Work/a.md", &note);
+        journal.write("a.md", &note);
+        let work = || Some("Work".to_string());
+
+        set_pinned("a.md".into(), true, work()).unwrap();
+        set_entry_date("a.md".into(), "March 2, 2024".into(), work()).unwrap();
+        assert_eq!(
+            rename_tag("idea".into(), "ideas".into(), work()).unwrap(),
+            1
+        );
+
+        let updated = journal.read("Work/a.md");
+        assert!(updated.contains("pinned: true"));
+        assert!(updated.contains("date: March 2, 2024"));
+        assert_eq!(parse_entry_tags(&updated), ["ideas"]);
+        assert_eq!(journal.read("a.md"), note);
+
+        assert_eq!(list_all_tags(work()).unwrap(), ["ideas"]);
+        assert_eq!(list_all_tags(None).unwrap(), ["idea"]);
+        assert_eq!(
+            list_entries_by_tag("ideas".into(), work()).unwrap().len(),
+            1
+        );
+        assert!(list_entries_by_tag("ideas".into(), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn trashed_notebook_entry_restores_into_its_notebook() {
+        let journal = TestJournal::new();
+        journal.write("Work/a.md", "work");
+        journal.write("a.md", "top level");
+
+        delete_entry("a.md".into(), Some("Work".into())).unwrap();
+        assert!(notebook_filenames("Work").is_empty());
+
+        assert_eq!(trash::restore_entry("a.md".into()).unwrap(), "a.md");
+        assert_eq!(journal.read("Work/a.md"), "work");
+        assert_eq!(journal.read("a.md"), "top level");
+    }
+
+    #[test]
+    fn batch_reports_valid_and_invalid_files() {
+        let journal = TestJournal::new();
+        let valid = journal.outside("valid.wav");
+        audio_recorder::write_wav(&[0.25; 16000], 16000, &valid).unwrap();
+        let paths = vec![
+            valid.to_string_lossy().into_owned(),
+            journal
+                .outside("missing.wav")
+                .to_string_lossy()
+                .into_owned(),
+        ];
+
+        let mut statuses = Vec::new();
+        let results = transcribe_batch(
+            &paths,
+            |path| {
+                audio_import::convert_to_whisper_format(path)
+                    .map(|samples| format!("{} samples", samples.len()))
+            },
+            |progress| statuses.push((progress.index, progress.status)),
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].filename, "valid.wav");
+        assert_eq!(results[0].transcript.as_deref(), Some("16000 samples"));
+        assert_eq!(results[0].error, None);
+        assert_eq!(results[1].filename, "missing.wav");
+        assert_eq!(results[1].transcript, None);
+        assert_eq!(results[1].error.as_deref(), Some("Audio file not found"));
+        assert_eq!(
+            statuses,
+            [
+                (0, "transcribing"),
+                (0, "done"),
+                (1, "transcribing"),
+                (1, "failed")
+            ]
+        );
+    }
+
+    #[test]
+    fn excerpt_strips_markdown() {
+        let content = entry(
+            "Plans",
+            "March 1, 2024",
+            &["work"],
+            "# Weekly plan\n\nShip the **beta** and read [the docs](https://example.com).\n\n> Stay *un*hurried.\n",
+        );
+
+        assert_eq!(
+            make_excerpt(&content),
+            "Weekly plan Ship the beta and read the docs. Stay unhurried."
+        );
+        assert_eq!(make_excerpt(&entry("Empty", "March 1, 2024", &[], "")), "");
+
+        let long = make_excerpt(&"word ".repeat(100));
+        assert!(long.ends_with("word…"));
+        assert!(long.chars().count() <= EXCERPT_CHARS + 1);
+    }
+
+    #[test]
+    fn tag_counts_merge_case_variants() {
+        let journal = TestJournal::new();
+        journal.write("a.md", &entry("A", "March 1, 2024", &["Work", "ideas"], ""));
+        journal.write("b.md", &entry("B", "March 2, 2024", &["work"], ""));
+        journal.write("c.md", &entry("C", "March 3, 2024", &["WORK", "work"], ""));
+        journal.write("d.md", &entry("D", "March 4, 2024", &["books"], ""));
+
+        let counts: Vec<(String, String, usize)> = tag_counts(None)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.tag, c.display, c.count))
+            .collect();
+
+        assert_eq!(
+            counts,
+            [
+                ("work".into(), "Work".into(), 3),
+                ("books".into(), "books".into(), 1),
+                ("ideas".into(), "ideas".into(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_tag_rewrites_only_entries_with_it() {
+        let journal = TestJournal::new();
+        journal.write(
+            "a.md",
+            &entry("A", "March 1, 2024", &["idea", "work"], "First *body*."),
+        );
+        journal.write(
+            "b.md",
+            &entry("B", "March 2, 2024", &["Idea", "ideas"], "Second."),
+        );
+        let unrelated = entry("C", "March 3, 2024", &["ideabook"], "Untouched.\n");
+        journal.write("c.md", &unrelated);
+
+        assert_eq!(rename_tag("idea".into(), "ideas".into(), None).unwrap(), 2);
+
+        let a = journal.read("a.md");
+        assert_eq!(parse_entry_tags(&a), ["ideas", "work"]);
+        assert_eq!(parse_frontmatter(&a), ("A".into(), "March 1, 2024".into()));
+        assert!(a.ends_with("First *body*."));
+
+        // Already had the new tag, so it isn't listed twice
+        assert_eq!(parse_entry_tags(&journal.read("b.md")), ["ideas"]);
+        assert_eq!(journal.read("c.md"), unrelated);
+    }
+
+    #[test]
+    fn bulk_delete_reports_missing_files() {
+        let journal = TestJournal::new();
+        journal.write("a.md", &entry("A", "March 1, 2024", &[], ""));
+        journal.write("b.md", &entry("B", "March 2, 2024", &[], ""));
+        journal.write("keep.md", &entry("Keep", "March 3, 2024", &[], ""));
+        // Listed first so the cache holds every entry
+        assert_eq!(list_entries(None).unwrap().len(), 3);
+
+        let result = delete_entries(
+            vec!["a.md".into(), "missing.md".into(), "b.md".into()],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(result.deleted, ["a.md", "b.md"]);
+        assert_eq!(
+            result.failed,
+            [("missing.md".to_string(), "File does not exist".to_string())]
+        );
+
+        let remaining: Vec<String> = list_entries(None)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect();
+        assert_eq!(remaining, ["keep.md"]);
+        assert_eq!(trash::list_trash().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn second_entry_of_the_day_gets_its_own_file() {
+        let journal = TestJournal::new();
+
+        let first = create_entry(None).unwrap();
+        assert_eq!(first, format!("{}.md", today()));
+        fs::write(journal.root().join(&first), "Morning entry").unwrap();
+
+        let second = create_entry(None).unwrap();
+        assert_eq!(second, format!("{} (2).md", today()));
+        assert_eq!(journal.read(&first), "Morning entry");
+        assert_eq!(listed(&journal.root()).len(), 2);
+    }
+
+    #[test]
+    fn default_template_entry_is_dated_today() {
+        let journal = TestJournal::new();
+        journal.write(
+            "templates/Daily.md",
+            "---\ntitle: Daily\ndate: someday\n---\n\n## Plan\n",
+        );
+        templates::set_notebook_template(None, Some("Daily".into())).unwrap();
+
+        let filename = create_entry(None).unwrap();
+        let content = journal.read(&filename);
+        let fields = frontmatter::parse(&content);
+
+        assert_eq!(frontmatter::get_str(&fields, "date"), Some(today()));
+        assert!(content.starts_with("---\ntitle: Daily\n"));
+        assert!(content.ends_with("## Plan\n"));
+    }
+
+    #[test]
+    fn untranslated_transcript_clears_the_spoken_language() {
+        let journal = TestJournal::new();
+        journal.write("trip.md", &entry("Trip", "March 1, 2024", &[], "Body"));
+
+        let spanish = TranscriptLanguages {
+            spoken: "es",
+            text: "en",
+        };
+        stamp_transcription("trip.md", None, Some(&spanish)).unwrap();
+        assert!(journal.read("trip.md").contains("spoken_language: es"));
+
+        stamp_transcription("trip.md", None, Some(&ENGLISH)).unwrap();
+        let content = journal.read("trip.md");
+        assert!(!content.contains("spoken_language"));
+        assert!(content.contains("language: en"));
+    }
+
+    #[test]
+    fn metadata_writes_refuse_invalid_frontmatter() {
+        let journal = TestJournal::new();
+        let content = "---\ntitle: Meeting: notes\ntags:\n  - work\n---\n\nAgenda";
+        journal.write("meeting.md", content);
+
+        assert!(set_pinned("meeting.md".into(), true, None).is_err());
+        assert!(set_entry_date("meeting.md".into(), "March 1, 2024".into(), None).is_err());
+        assert_eq!(journal.read("meeting.md"), content);
+    }
+
+    #[test]
+    fn saving_metadata_keeps_other_fields_as_written() {
+        let journal = TestJournal::new();
+        journal.write(
+            "Trip.md",
+            "---\ntitle: Trip\ndate: March 1, 2024\n# packing list below\nitems:\n  - tent\n  - stove\nmood: calm\n---\n\nOld body",
+        );
+
+        update_entry_metadata(
+            "Trip.md".into(),
+            "Trip".into(),
+            "March 2, 2024".into(),
+            "New body".into(),
+            Some(String::new()),
+            Some("Lake".into()),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            journal.read("Trip.md"),
+            "---\ntitle: Trip\ndate: March 2, 2024\n# packing list below\nitems:\n  - tent\n  - stove\nlocation: Lake\n---\n\nNew body"
+        );
+    }
 }
@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::frontmatter;
+use crate::{
+    build_entry_info, parse_frontmatter, read_journal_files, read_notebook_files, EntryInfo,
+};
+
+#[derive(Serialize)]
+pub struct LinkCounts {
+    filename: String,
+    outbound: usize,
+    backlinks: usize,
+}
+
+/// Normalize a link target or entry name for case-insensitive matching
+fn normalize_target(target: &str) -> String {
+    let target = target.trim().to_lowercase();
+    target.strip_suffix(".md").unwrap_or(&target).to_string()
+}
+
+/// Targets of all `[[target]]` and `[[target|alias]]` links in a body
+pub fn extract_wiki_links(body: &str) -> Vec<String> {
+    let link_regex = regex::Regex::new(r"\[\[([^\]|]+)(?:\|[^\]]*)?\]\]").unwrap();
+
+    link_regex
+        .captures_iter(body)
+        .filter_map(|c| c.get(1))
+        .map(|m| normalize_target(m.as_str()))
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+/// Outbound link and backlink counts for every entry in the journal
+pub fn compute_link_counts() -> Result<Vec<LinkCounts>, String> {
+    let files = read_journal_files()?;
+
+    // Links may name an entry by filename (with or without .md) or by title
+    let mut names: HashMap<String, usize> = HashMap::new();
+    for (i, (filename, content)) in files.iter().enumerate() {
+        names.insert(normalize_target(filename), i);
+
+        let (title, _) = parse_frontmatter(content);
+        if !title.is_empty() {
+            names.entry(normalize_target(&title)).or_insert(i);
+        }
+    }
+
+    let mut outbound = vec![0; files.len()];
+    let mut linked_from: Vec<HashSet<usize>> = vec![HashSet::new(); files.len()];
+
+    for (i, (_, content)) in files.iter().enumerate() {
+        let links = extract_wiki_links(frontmatter::body(content));
+        outbound[i] = links.len();
+
+        for target in links {
+            if let Some(&j) = names.get(&target) {
+                if j != i {
+                    linked_from[j].insert(i);
+                }
+            }
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .enumerate()
+        .map(|(i, (filename, _))| LinkCounts {
+            filename,
+            outbound: outbound[i],
+            backlinks: linked_from[i].len(),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_link_counts() -> Result<Vec<LinkCounts>, String> {
+    let mut counts = compute_link_counts()?;
+    counts.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(counts)
+}
+
+/// Entries with no outbound wiki-links and no backlinks
+#[tauri::command]
+pub fn list_orphan_entries() -> Result<Vec<String>, String> {
+    let mut orphans: Vec<String> = compute_link_counts()?
+        .into_iter()
+        .filter(|c| c.outbound == 0 && c.backlinks == 0)
+        .map(|c| c.filename)
+        .collect();
+
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Entries whose wiki-links point at the given entry, by filename or title
+#[tauri::command]
+pub fn get_backlinks(filename: String, notebook: Option<String>) -> Result<Vec<EntryInfo>, String> {
+    let files = read_notebook_files(notebook.as_deref())?;
+
+    let target = match files.iter().find(|(name, _)| *name == filename) {
+        Some((_, content)) => content,
+        None => return Err("File does not exist".to_string()),
+    };
+
+    let mut names = HashSet::new();
+    names.insert(normalize_target(&filename));
+    let (title, _) = parse_frontmatter(target);
+    if !title.is_empty() {
+        names.insert(normalize_target(&title));
+    }
+
+    let mut backlinks: Vec<EntryInfo> = files
+        .iter()
+        .filter(|(name, _)| *name != filename)
+        .filter(|(_, content)| {
+            extract_wiki_links(frontmatter::body(content))
+                .iter()
+                .any(|link| names.contains(link))
+        })
+        .map(|(name, content)| build_entry_info(name, content, content.len() as u64))
+        .collect();
+
+    backlinks.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(backlinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TestJournal};
+
+    #[test]
+    fn extracts_targets_and_aliases() {
+        assert_eq!(
+            extract_wiki_links("See [[Trip.md]] and [[ trip plans |the plan]], not [[]]."),
+            ["trip", "trip plans"]
+        );
+    }
+
+    #[test]
+    fn backlinks_come_from_linking_entries_only() {
+        let journal = TestJournal::new();
+        journal.write(
+            "trip.md",
+            &entry("Trip Plans", "March 1, 2024", &[], "Packing."),
+        );
+        journal.write(
+            "a.md",
+            &entry("A", "March 2, 2024", &[], "Back from [[trip]]."),
+        );
+        journal.write(
+            "b.md",
+            &entry("B", "March 3, 2024", &[], "Per [[Trip Plans|the plan]]."),
+        );
+        journal.write("c.md", &entry("C", "March 4, 2024", &[], "Nothing linked."));
+
+        let backlinks: Vec<String> = get_backlinks("trip.md".into(), None)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect();
+        assert_eq!(backlinks, ["a.md", "b.md"]);
+
+        assert_eq!(list_orphan_entries().unwrap(), ["c.md"]);
+    }
+}
@@ -0,0 +1,242 @@
+use tauri::{Emitter, Window};
+
+use crate::audio_analysis::rms;
+use crate::transcription::{join_segments, transcribe_segments, TranscribeOptions};
+
+const SAMPLE_RATE: usize = 16000;
+// Whisper works on 30-second windows, so chunks of about that length cost nothing extra
+const CHUNK_SAMPLES: usize = 30 * SAMPLE_RATE;
+// Audio shared by neighbouring chunks so a word on the cut is heard whole at least once
+const OVERLAP_SAMPLES: usize = SAMPLE_RATE;
+// How far before the nominal boundary to look for a quiet place to cut
+const CUT_SEARCH_SAMPLES: usize = 2 * SAMPLE_RATE;
+const CUT_FRAME_SAMPLES: usize = 320;
+// Longest run of words checked when removing text repeated across the overlap
+const MAX_OVERLAP_WORDS: usize = 12;
+// A single shared word is as likely to be chance ("the", "and") as real overlap
+const MIN_OVERLAP_WORDS: usize = 2;
+
+/// Start of the quietest 20ms frame in samples[from..to], to cut a chunk there
+fn quietest_point(samples: &[f32], from: usize, to: usize) -> usize {
+    samples[from..to]
+        .chunks(CUT_FRAME_SAMPLES)
+        .enumerate()
+        .min_by(|(_, a), (_, b)| rms(a).total_cmp(&rms(b)))
+        .map_or(to, |(i, _)| from + i * CUT_FRAME_SAMPLES)
+}
+
+/// Word compared across the overlap, ignoring case and punctuation
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Append next to transcript, dropping the words at the start of next that repeat
+/// the end of transcript (the audio both chunks heard). Runs shorter than
+/// MIN_OVERLAP_WORDS are kept.
+fn stitch(transcript: &mut String, next: &str) {
+    let previous: Vec<String> = transcript.split_whitespace().map(normalize_word).collect();
+    let next_words: Vec<&str> = next.split_whitespace().collect();
+    let next_normalized: Vec<String> = next_words.iter().map(|w| normalize_word(w)).collect();
+
+    let longest = MAX_OVERLAP_WORDS.min(previous.len()).min(next_words.len());
+    let repeated = (MIN_OVERLAP_WORDS..=longest)
+        .rev()
+        .find(|&n| previous[previous.len() - n..] == next_normalized[..n])
+        .unwrap_or(0);
+
+    let rest = next_words[repeated..].join(" ");
+    if rest.is_empty() {
+        return;
+    }
+    if !transcript.is_empty() {
+        transcript.push(' ');
+    }
+    transcript.push_str(&rest);
+}
+
+/// Transcribe long audio (16kHz mono f32) in ~30-second chunks, cut at quiet points
+/// where possible and overlapping slightly, so memory stays flat. After each chunk
+/// emits `transcription-progress` (0-100) and `transcription-partial` with the
+/// transcript so far, so a failure late in the file doesn't cost the whole run.
+pub fn transcribe_long(samples: &[f32], window: &Window) -> Result<String, String> {
+    let options = TranscribeOptions::default();
+
+    transcribe_chunks(
+        samples,
+        |chunk| transcribe_segments(chunk, &options).map(|segments| join_segments(&segments)),
+        |transcript, percent| {
+            let _ = window.emit("transcription-progress", percent);
+            let _ = window.emit("transcription-partial", transcript);
+        },
+    )
+}
+
+/// Run transcribe over each chunk of samples in turn, stitching the results and
+/// reporting the transcript so far after each one. Stops at the first chunk that
+/// fails, naming where in the audio it was; earlier chunks have been reported.
+fn transcribe_chunks<T, E>(
+    samples: &[f32],
+    mut transcribe: T,
+    mut emit: E,
+) -> Result<String, String>
+where
+    T: FnMut(&[f32]) -> Result<String, String>,
+    E: FnMut(&str, u8),
+{
+    if samples.is_empty() {
+        return Err("No audio samples provided".to_string());
+    }
+
+    let mut transcript = String::new();
+    let mut start = 0;
+
+    loop {
+        let nominal_end = start + CHUNK_SAMPLES;
+        let end = if nominal_end >= samples.len() {
+            samples.len()
+        } else {
+            quietest_point(samples, nominal_end - CUT_SEARCH_SAMPLES, nominal_end)
+        };
+        let chunk_end = (end + OVERLAP_SAMPLES).min(samples.len());
+
+        let text = transcribe(&samples[start..chunk_end])
+            .map_err(|e| format!("Transcription stopped at {}s: {}", start / SAMPLE_RATE, e))?;
+        stitch(&mut transcript, &text);
+
+        emit(&transcript, (chunk_end * 100 / samples.len()) as u8);
+
+        if chunk_end == samples.len() {
+            break;
+        }
+        start = end;
+    }
+
+    if transcript.is_empty() {
+        return Err("No speech detected in the audio".to_string());
+    }
+
+    Ok(transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::Segment;
+
+    fn segments(texts: &[&str]) -> Vec<Segment> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| Segment {
+                index,
+                start_ms: index as i64 * 1000,
+                end_ms: index as i64 * 1000 + 900,
+                text: text.to_string(),
+                no_speech_prob: None,
+                confidence: 1.0,
+                low_confidence: false,
+                tokens: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stitching_drops_the_repeated_overlap() {
+        let first = segments(&[" We met at the station", " and walked to the river."]);
+        let second = segments(&[" Walked to the river,", " then home again."]);
+
+        let mut transcript = String::new();
+        stitch(&mut transcript, &join_segments(&first));
+        stitch(&mut transcript, &join_segments(&second));
+
+        assert_eq!(
+            transcript,
+            "We met at the station and walked to the river. then home again."
+        );
+    }
+
+    #[test]
+    fn stitching_without_overlap_keeps_everything() {
+        let mut transcript = "One two three.".to_string();
+        stitch(&mut transcript, "Four five.");
+        assert_eq!(transcript, "One two three. Four five.");
+
+        // A chunk that only repeats the overlap adds nothing
+        stitch(&mut transcript, "four, five");
+        assert_eq!(transcript, "One two three. Four five.");
+    }
+
+    #[test]
+    fn stitching_keeps_a_single_repeated_word() {
+        let mut transcript = "We walked to the".to_string();
+        stitch(&mut transcript, "the end of the road.");
+        assert_eq!(transcript, "We walked to the the end of the road.");
+    }
+
+    #[test]
+    fn failed_chunk_keeps_the_transcript_so_far() {
+        let samples = vec![0.0; CHUNK_SAMPLES * 3];
+        let mut calls = 0;
+        let mut reported = Vec::new();
+
+        let result = transcribe_chunks(
+            &samples,
+            |_| {
+                calls += 1;
+                match calls {
+                    1 => Ok("first chunk".to_string()),
+                    2 => Ok("second chunk".to_string()),
+                    _ => Err("model crashed".to_string()),
+                }
+            },
+            |transcript, percent| reported.push((transcript.to_string(), percent)),
+        );
+
+        // Silence cuts each chunk at the start of its search window, 2s early
+        assert_eq!(
+            result.unwrap_err(),
+            "Transcription stopped at 56s: model crashed"
+        );
+        assert_eq!(calls, 3);
+        assert_eq!(reported.len(), 2);
+        assert_eq!(reported[1].0, "first chunk second chunk");
+        assert!(reported[0].1 < reported[1].1);
+    }
+
+    #[test]
+    fn chunks_cover_the_audio_and_end_at_100() {
+        let samples = vec![0.0; CHUNK_SAMPLES * 2 + SAMPLE_RATE];
+        let mut covered = 0;
+        let mut calls = 0;
+        let mut last_percent = 0;
+
+        let transcript = transcribe_chunks(
+            &samples,
+            |chunk| {
+                covered += chunk.len();
+                calls += 1;
+                Ok(format!("chunk {}", calls))
+            },
+            |_, percent| last_percent = percent,
+        )
+        .unwrap();
+
+        assert!(covered >= samples.len());
+        assert_eq!(last_percent, 100);
+        assert_eq!(transcript, "chunk 1 chunk 2 chunk 3");
+    }
+
+    #[test]
+    fn cuts_at_the_quietest_frame() {
+        let mut samples = vec![0.5; 4 * CUT_FRAME_SAMPLES];
+        samples[2 * CUT_FRAME_SAMPLES..3 * CUT_FRAME_SAMPLES].fill(0.01);
+
+        assert_eq!(
+            quietest_point(&samples, 0, samples.len()),
+            2 * CUT_FRAME_SAMPLES
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::frontmatter;
+use crate::{
+    entry_date_format, get_journal_dir, parse_entry_date_with, read_journal_files, unique_filename,
+    write_atomic,
+};
+
+#[derive(Serialize)]
+pub struct DateFix {
+    filename: String,
+    old_date: String,
+    new_date: String,
+}
+
+// Extensions that are always Markdown, just not the one list_entries looks for
+const MARKDOWN_EXTENSIONS: &[&str] = &["markdown", "mdown", "mkd", "mkdn"];
+// Plain-text extensions that may hold notes
+const TEXT_EXTENSIONS: &[&str] = &["txt", "text"];
+
+/// Heuristic for plain text that was written as Markdown
+fn looks_like_markdown(content: &str) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("# ")
+            || line.starts_with("## ")
+            || line.starts_with("- ")
+            || line.starts_with("* ")
+            || line.starts_with("> ")
+            || line.contains("](")
+    })
+}
+
+/// Journal files that look like notes but don't have the .md extension
+fn nonstandard_note_paths(journal_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut notes = Vec::new();
+
+    for entry in fs::read_dir(journal_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let ext = match path.extension().and_then(|s| s.to_str()) {
+            Some(ext) => ext.to_lowercase(),
+            None => continue,
+        };
+
+        let is_note = if MARKDOWN_EXTENSIONS.contains(&ext.as_str()) {
+            true
+        } else if TEXT_EXTENSIONS.contains(&ext.as_str()) {
+            // Only claim text files that read as notes
+            fs::read_to_string(&path)
+                .map(|content| {
+                    frontmatter::has_frontmatter(&content) || looks_like_markdown(&content)
+                })
+                .unwrap_or(false)
+        } else {
+            false
+        };
+
+        if is_note {
+            notes.push(path);
+        }
+    }
+
+    notes.sort();
+    Ok(notes)
+}
+
+#[tauri::command]
+pub fn find_nonstandard_notes() -> Result<Vec<String>, String> {
+    let journal_dir = get_journal_dir();
+
+    Ok(nonstandard_note_paths(&journal_dir)?
+        .iter()
+        .filter_map(|p| p.file_name().and_then(|s| s.to_str()).map(String::from))
+        .collect())
+}
+
+/// Rename nonstandard notes to .md, suffixing names that would collide.
+/// Returns the new filenames.
+#[tauri::command]
+pub fn normalize_extensions() -> Result<Vec<String>, String> {
+    let journal_dir = get_journal_dir();
+    let mut renamed = Vec::new();
+
+    for path in nonstandard_note_paths(&journal_dir)? {
+        let stem = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => stem,
+            None => continue,
+        };
+
+        let new_filename = unique_filename(&journal_dir, stem);
+        fs::rename(&path, journal_dir.join(&new_filename)).map_err(|e| e.to_string())?;
+        renamed.push(new_filename);
+    }
+
+    Ok(renamed)
+}
+
+/// Parse datetime-style and ISO dates that the canonical date parser rejects
+fn parse_datetime_date(value: &str) -> Option<NaiveDate> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.date_naive());
+    }
+
+    [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .map(|dt| dt.date())
+    .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+}
+
+/// Rewrite datetime-valued `date` fields into the canonical date format, keeping the
+/// original value as `created` when the entry has none. With dry_run nothing is written.
+#[tauri::command]
+pub fn normalize_dates(dry_run: bool) -> Result<Vec<DateFix>, String> {
+    let journal_dir = get_journal_dir();
+    let date_format = entry_date_format();
+    let mut fixes = Vec::new();
+
+    for (filename, content) in read_journal_files()? {
+        if !frontmatter::has_frontmatter(&content) {
+            continue;
+        }
+        let fields = frontmatter::parse(&content);
+
+        let old_date = match frontmatter::get_str(&fields, "date") {
+            Some(date)
+                if !date.is_empty() && parse_entry_date_with(&date, &date_format).is_none() =>
+            {
+                date
+            }
+            _ => continue,
+        };
+
+        let new_date = match parse_datetime_date(old_date.trim_matches(|c| c == '"' || c == '\'')) {
+            Some(date) => date.format(&date_format).to_string(),
+            None => continue,
+        };
+
+        if !dry_run {
+            let mut updated = frontmatter::set_field(&content, "date", &new_date)
+                .map_err(|e| format!("{}: {}", filename, e))?;
+            if frontmatter::get_str(&fields, "created").is_none() {
+                updated = frontmatter::set_field(&updated, "created", &old_date)
+                    .map_err(|e| format!("{}: {}", filename, e))?;
+            }
+            write_atomic(&journal_dir.join(&filename), &updated)?;
+        }
+
+        fixes.push(DateFix {
+            filename,
+            old_date,
+            new_date,
+        });
+    }
+
+    fixes.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(fixes)
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Local};
+use serde_yaml::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::frontmatter::{self, Frontmatter};
+use crate::{entry_date_format, get_journal_dir, sanitize_filename, unique_filename, write_atomic};
+
+const IMPORTABLE_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// Text of the first `# heading` in a body
+fn first_heading(body: &str) -> Option<String> {
+    body.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|heading| heading.trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+/// Give a note without frontmatter the standard header, titled from its first
+/// heading (or its filename) and dated from its modification time
+fn with_frontmatter(content: String, path: &Path, stem: &str) -> String {
+    if frontmatter::has_frontmatter(&content) {
+        return content;
+    }
+
+    let modified: DateTime<Local> = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::from)
+        .unwrap_or_else(|_| Local::now());
+    let title = first_heading(&content).unwrap_or_else(|| stem.to_string());
+
+    let mut fields = Frontmatter::new();
+    fields.insert("title".to_string(), Value::String(title));
+    fields.insert(
+        "date".to_string(),
+        Value::String(modified.format(&entry_date_format()).to_string()),
+    );
+
+    frontmatter::render(&fields, &format!("\n{}", content))
+}
+
+/// Copy markdown files into the journal, adding frontmatter where it's missing.
+/// Files that aren't markdown or can't be read are skipped. Returns the new filenames.
+#[tauri::command]
+pub fn import_markdown(paths: Vec<String>) -> Result<Vec<String>, String> {
+    let journal_dir = get_journal_dir();
+    fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
+
+    let mut imported = Vec::new();
+
+    for path in paths {
+        let path = Path::new(&path);
+
+        let is_markdown = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| IMPORTABLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if !is_markdown {
+            eprintln!("Skipping non-markdown file: {}", path.display());
+            continue;
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let safe_stem = match sanitize_filename(stem) {
+            s if s.is_empty() => "Imported note".to_string(),
+            s => s,
+        };
+
+        let filename = unique_filename(&journal_dir, &safe_stem);
+        write_atomic(
+            &journal_dir.join(&filename),
+            &with_frontmatter(content, path, stem),
+        )?;
+        imported.push(filename);
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, set_mtime, TestJournal};
+    use chrono::TimeZone;
+
+    #[test]
+    fn imports_with_and_without_frontmatter() {
+        let journal = TestJournal::new();
+
+        let existing = entry("Kept as is", "January 2, 2023", &["imported"], "Body");
+        let with = journal.outside("with.md");
+        fs::write(&with, &existing).unwrap();
+
+        let plain = journal.outside("Plain Note.markdown");
+        fs::write(&plain, "# Groceries\n\n- milk\n").unwrap();
+        let modified = Local.with_ymd_and_hms(2023, 5, 4, 12, 0, 0).unwrap();
+        set_mtime(&plain, modified.into());
+
+        let text = journal.outside("notes.txt");
+        fs::write(&text, "not markdown").unwrap();
+
+        let paths = [&with, &plain, &text]
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        let imported = import_markdown(paths).unwrap();
+
+        assert_eq!(imported, ["with.md", "Plain Note.md"]);
+        assert_eq!(journal.read("with.md"), existing);
+
+        let content = journal.read("Plain Note.md");
+        let fields = frontmatter::parse(&content);
+        assert_eq!(
+            frontmatter::get_str(&fields, "title").as_deref(),
+            Some("Groceries")
+        );
+        assert_eq!(
+            frontmatter::get_str(&fields, "date").as_deref(),
+            Some("May 4, 2023")
+        );
+        assert!(content.ends_with("# Groceries\n\n- milk\n"));
+        assert!(!journal.root().join("notes.md").exists());
+    }
+}
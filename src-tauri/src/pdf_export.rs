@@ -0,0 +1,267 @@
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::export::ensure_parent_exists;
+use crate::frontmatter;
+use crate::{notebook_dir, parse_frontmatter};
+
+// A4 portrait, in millimetres
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const PT_TO_MM: f32 = 0.3528;
+// Rough average glyph width of the builtin fonts, as a fraction of the font size
+const CHAR_WIDTH: f32 = 0.5;
+
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.5;
+const TITLE_SIZE: f32 = 22.0;
+const LIST_INDENT: f32 = 6.0;
+
+/// Block-level pieces of a markdown body, flattened to plain text
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Item {
+        depth: usize,
+        marker: String,
+        text: String,
+    },
+    Code(String),
+}
+
+fn push_item(
+    blocks: &mut Vec<Block>,
+    text: &mut String,
+    marker: &mut Option<String>,
+    depth: usize,
+) {
+    if let Some(marker) = marker.take() {
+        blocks.push(Block::Item {
+            depth,
+            marker,
+            text: std::mem::take(text).trim().to_string(),
+        });
+    }
+}
+
+fn collect_blocks(body: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    // Next number for each open list, None for bullet lists
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut item_marker: Option<String> = None;
+
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::List(start)) => {
+                // A nested list ends the text of the item it sits in
+                push_item(
+                    &mut blocks,
+                    &mut text,
+                    &mut item_marker,
+                    lists.len().saturating_sub(1),
+                );
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                item_marker = Some(match lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "•".to_string(),
+                });
+            }
+            Event::End(TagEnd::Item) => {
+                push_item(
+                    &mut blocks,
+                    &mut text,
+                    &mut item_marker,
+                    lists.len().saturating_sub(1),
+                );
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                blocks.push(Block::Heading(level as usize, std::mem::take(&mut text)));
+            }
+            Event::End(TagEnd::Paragraph) => {
+                // Paragraphs inside loose list items stay part of the item
+                if item_marker.is_some() {
+                    text.push(' ');
+                } else {
+                    blocks.push(Block::Paragraph(std::mem::take(&mut text)));
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                blocks.push(Block::Code(std::mem::take(&mut text)));
+            }
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+/// Greedy word wrap to at most max_chars per line
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    mono: IndirectFontRef,
+}
+
+/// Lays lines out top to bottom, starting a new page when one fills up
+struct PageWriter<'a> {
+    doc: &'a PdfDocumentReference,
+    layer: PdfLayerReference,
+    y: f32,
+}
+
+impl PageWriter<'_> {
+    fn line(&mut self, text: &str, size: f32, indent: f32, font: &IndirectFontRef) {
+        let height = size * PT_TO_MM * 1.4;
+        if self.y - height < MARGIN {
+            let (page, layer) = self
+                .doc
+                .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+
+        self.y -= height;
+        self.layer
+            .use_text(text, size, Mm(MARGIN + indent), Mm(self.y), font);
+    }
+
+    fn wrapped(&mut self, text: &str, size: f32, indent: f32, font: &IndirectFontRef) {
+        let max_chars =
+            ((PAGE_WIDTH - 2.0 * MARGIN - indent) / (size * PT_TO_MM * CHAR_WIDTH)) as usize;
+        for line in wrap(text, max_chars.max(1)) {
+            self.line(&line, size, indent, font);
+        }
+    }
+
+    fn gap(&mut self, mm: f32) {
+        self.y -= mm;
+    }
+}
+
+fn heading_size(level: usize) -> f32 {
+    match level {
+        1 => 18.0,
+        2 => 15.0,
+        3 => 13.0,
+        _ => BODY_SIZE,
+    }
+}
+
+/// Render an entry's title and markdown body to a PDF at out_path
+#[tauri::command]
+pub fn export_entry_pdf(
+    filename: String,
+    out_path: String,
+    notebook: Option<String>,
+) -> Result<(), String> {
+    let out_path = Path::new(&out_path);
+    ensure_parent_exists(out_path)?;
+
+    let content = fs::read_to_string(notebook_dir(notebook.as_deref())?.join(&filename))
+        .map_err(|e| e.to_string())?;
+    let (title, _) = parse_frontmatter(&content);
+    let title = if title.is_empty() {
+        filename.trim_end_matches(".md").to_string()
+    } else {
+        title
+    };
+
+    let (doc, page, layer) = PdfDocument::new(&title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let font_error = |e: printpdf::Error| format!("Failed to load PDF font: {}", e);
+    let fonts = Fonts {
+        regular: doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(font_error)?,
+        bold: doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(font_error)?,
+        mono: doc
+            .add_builtin_font(BuiltinFont::Courier)
+            .map_err(font_error)?,
+    };
+
+    let mut writer = PageWriter {
+        doc: &doc,
+        layer: doc.get_page(page).get_layer(layer),
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    // An empty body still produces a page with just the title
+    writer.wrapped(&title, TITLE_SIZE, 0.0, &fonts.bold);
+    writer.gap(4.0);
+
+    for block in collect_blocks(frontmatter::body(&content)) {
+        match block {
+            Block::Heading(level, text) => {
+                writer.gap(2.0);
+                writer.wrapped(&text, heading_size(level), 0.0, &fonts.bold);
+                writer.gap(1.0);
+            }
+            Block::Paragraph(text) => {
+                writer.wrapped(&text, BODY_SIZE, 0.0, &fonts.regular);
+                writer.gap(2.0);
+            }
+            Block::Item {
+                depth,
+                marker,
+                text,
+            } => {
+                let indent = LIST_INDENT * (depth + 1) as f32;
+                writer.wrapped(
+                    &format!("{} {}", marker, text),
+                    BODY_SIZE,
+                    indent,
+                    &fonts.regular,
+                );
+            }
+            Block::Code(text) => {
+                for line in text.trim_end().lines() {
+                    writer.line(line, CODE_SIZE, LIST_INDENT, &fonts.mono);
+                }
+                writer.gap(2.0);
+            }
+        }
+    }
+
+    let file = fs::File::create(out_path).map_err(|e| format!("Failed to create PDF: {}", e))?;
+    doc.save(&mut BufWriter::new(file))
+        .map_err(|e| format!("Failed to write PDF: {}", e))
+}
@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+use crate::frontmatter;
+use crate::{parse_frontmatter, read_notebook_files};
+
+// Roughly how many characters of context a snippet shows around its match
+const SNIPPET_CHARS: usize = 120;
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    filename: String,
+    title: String,
+    /// Text around the first match, with the matched term wrapped in `**`
+    snippet: String,
+}
+
+/// Cut a window of about SNIPPET_CHARS around [start, end), marking the match.
+/// Whitespace is collapsed so the snippet reads as one line.
+fn make_snippet(text: &str, start: usize, end: usize) -> String {
+    let context = SNIPPET_CHARS.saturating_sub(text[start..end].chars().count()) / 2;
+
+    let preceding: Vec<char> = text[..start].chars().collect();
+    let skip = preceding.len().saturating_sub(context);
+    let mut before: String = preceding[skip..].iter().collect();
+    if skip > 0 {
+        before.insert(0, '…');
+    }
+
+    let mut following = text[end..].chars();
+    let mut after: String = following.by_ref().take(context).collect();
+    if following.next().is_some() {
+        after.push('…');
+    }
+
+    let snippet = format!("{}**{}**{}", before, &text[start..end], after);
+    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Case-insensitive search over entry titles and bodies. The frontmatter block
+/// itself is never searched, only the title field and the body below it.
+#[tauri::command]
+pub fn search_entries(query: String, notebook: Option<String>) -> Result<Vec<SearchHit>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query_regex = regex::RegexBuilder::new(&regex::escape(query))
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut hits = Vec::new();
+
+    for (filename, content) in read_notebook_files(notebook.as_deref())? {
+        let (title, _) = parse_frontmatter(&content);
+        let body = frontmatter::body(&content);
+
+        // Prefer body context for the snippet; fall back to the title when only it matches
+        let snippet = match query_regex.find(body) {
+            Some(m) => make_snippet(body, m.start(), m.end()),
+            None => match query_regex.find(&title) {
+                Some(m) => make_snippet(&title, m.start(), m.end()),
+                None => continue,
+            },
+        };
+
+        hits.push(SearchHit {
+            filename,
+            title,
+            snippet,
+        });
+    }
+
+    hits.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(hits)
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::whisper_model::WhisperModel;
+
+pub const APP_IDENTIFIER: &str = "com.sijokuruvilla.flow";
+const CONFIG_FILENAME: &str = "config.json";
+pub const DEFAULT_DATE_FORMAT: &str = "%B %-d, %Y";
+
+/// OS scheduling priority for transcription work
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionPriority {
+    #[default]
+    Normal,
+    Low,
+}
+
+/// Persisted app settings, stored as config.json in the app config dir
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Folder holding the journal's entries; None uses the default location
+    pub journal_dir: Option<String>,
+    /// chrono format string for the dates of new entries and their filenames
+    pub date_format: String,
+    /// Days to keep trashed entries before they are purged (0 keeps them forever)
+    pub trash_retention_days: u32,
+    /// Folder holding downloaded Whisper models; None uses the app data dir
+    pub models_dir: Option<String>,
+    /// The active Whisper model, stored by name, e.g. "base.en"
+    #[serde(deserialize_with = "model_or_default")]
+    pub whisper_model: WhisperModel,
+    /// Where the custom Whisper model is downloaded from
+    pub custom_model_url: Option<String>,
+    /// Run transcription below normal priority so it doesn't starve UI and audio threads
+    pub transcription_priority: TranscriptionPriority,
+    /// Whisper worker threads; None picks a count from the CPU cores
+    pub transcription_threads: Option<usize>,
+    /// Template new entries start from, keyed by notebook ("" for the top-level journal)
+    pub default_templates: BTreeMap<String, String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            journal_dir: None,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            trash_retention_days: 30,
+            models_dir: None,
+            whisper_model: WhisperModel::default(),
+            custom_model_url: None,
+            transcription_priority: TranscriptionPriority::Normal,
+            transcription_threads: None,
+            default_templates: BTreeMap::new(),
+        }
+    }
+}
+
+/// Read a model name, falling back to the default for names no longer offered so
+/// the rest of the settings still load
+fn model_or_default<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<WhisperModel, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Ok(WhisperModel::from_name(&name).unwrap_or_default())
+}
+
+// Config file tests on this thread use instead of the user's (see test_support)
+#[cfg(test)]
+thread_local! {
+    static TEST_CONFIG_PATH: std::cell::RefCell<Option<PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(test)]
+pub fn set_test_config_path(path: Option<PathBuf>) {
+    TEST_CONFIG_PATH.with(|p| *p.borrow_mut() = path);
+}
+
+pub fn get_config_path() -> PathBuf {
+    #[cfg(test)]
+    if let Some(path) = TEST_CONFIG_PATH.with(|p| p.borrow().clone()) {
+        return path;
+    }
+
+    let config_dir = dirs::config_dir().expect("Could not find config directory");
+    config_dir.join(APP_IDENTIFIER).join(CONFIG_FILENAME)
+}
+
+/// Load settings, using defaults when the file doesn't exist yet. A file that can't be
+/// read or parsed is an error, so nothing overwrites it with defaults.
+pub fn try_load_settings() -> Result<Settings, String> {
+    let path = get_config_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(format!("Failed to read settings: {}", e)),
+    };
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))
+}
+
+/// Load settings, falling back to defaults if the file is missing or invalid
+pub fn load_settings() -> Settings {
+    try_load_settings().unwrap_or_else(|e| {
+        eprintln!("{}; using default settings", e);
+        Settings::default()
+    })
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    let path = get_config_path();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Load settings, apply a change and persist the result. Fails without touching the
+/// file if the existing settings can't be parsed.
+pub fn update_settings<F: FnOnce(&mut Settings)>(change: F) -> Result<Settings, String> {
+    let mut settings = try_load_settings()?;
+    change(&mut settings);
+    save_settings(&settings)?;
+    Ok(settings)
+}
@@ -0,0 +1,508 @@
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+
+use crate::frontmatter;
+use crate::{notebook_dir, parse_entry_date, parse_entry_tags, read_journal_files};
+
+// Average silent reading speed
+const WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Serialize)]
+pub struct EntryStats {
+    word_count: usize,
+    char_count: usize,
+    reading_time_minutes: usize,
+}
+
+/// Count words in an entry body, ignoring tokens that are only markdown syntax
+pub fn count_words(body: &str) -> usize {
+    body.split_whitespace()
+        .filter(|word| word.chars().any(|c| c.is_alphanumeric()))
+        .count()
+}
+
+/// Characters of prose in a body, not counting line breaks or the heading,
+/// emphasis and quote markers that start a line
+fn count_chars(body: &str) -> usize {
+    body.lines()
+        .map(|line| {
+            line.trim_start_matches(|c: char| c == '#' || c == '*' || c == '>' || c.is_whitespace())
+                .trim_end()
+                .chars()
+                .count()
+        })
+        .sum()
+}
+
+#[tauri::command]
+pub fn entry_stats(filename: String, notebook: Option<String>) -> Result<EntryStats, String> {
+    let content = fs::read_to_string(notebook_dir(notebook.as_deref())?.join(&filename))
+        .map_err(|e| e.to_string())?;
+    let body = frontmatter::body(&content);
+    let word_count = count_words(body);
+
+    Ok(EntryStats {
+        word_count,
+        char_count: count_chars(body),
+        reading_time_minutes: word_count.div_ceil(WORDS_PER_MINUTE),
+    })
+}
+
+/// Number of entries per word-count bucket, as (bucket_start, count) pairs.
+/// Buckets are contiguous from zero up to the longest entry so the result charts directly.
+#[tauri::command]
+pub fn word_count_histogram(bucket_size: usize) -> Result<Vec<(usize, usize)>, String> {
+    if bucket_size == 0 {
+        return Err("Bucket size must be at least 1".to_string());
+    }
+
+    let mut counts: Vec<usize> = Vec::new();
+
+    for (_, content) in read_journal_files()? {
+        let bucket = count_words(frontmatter::body(&content)) / bucket_size;
+        if bucket >= counts.len() {
+            counts.resize(bucket + 1, 0);
+        }
+        counts[bucket] += 1;
+    }
+
+    Ok(counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (i * bucket_size, count))
+        .collect())
+}
+
+const STOPWORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "could",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "don't",
+    "down",
+    "during",
+    "each",
+    "few",
+    "for",
+    "from",
+    "further",
+    "get",
+    "got",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "i'm",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "it's",
+    "its",
+    "itself",
+    "just",
+    "like",
+    "me",
+    "more",
+    "most",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "really",
+    "same",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+    "also",
+    "much",
+    "many",
+    "one",
+    "even",
+    "still",
+    "http",
+    "https",
+    "www",
+    "com",
+];
+
+/// Lowercased word tokens of a body, splitting on anything that isn't part of a word
+fn tokenize(body: &str) -> impl Iterator<Item = String> + '_ {
+    body.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .map(|token| {
+            token
+                .trim_matches(|c| c == '\'' || c == '’')
+                .replace('’', "'")
+                .to_lowercase()
+        })
+        .filter(|token| token.chars().count() > 1 && !token.chars().all(|c| c.is_numeric()))
+}
+
+/// Most frequent non-stopword terms across all entry bodies, with their counts
+#[tauri::command]
+pub fn top_terms(limit: usize) -> Result<Vec<(String, usize)>, String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for (_, content) in read_journal_files()? {
+        for token in tokenize(frontmatter::body(&content)) {
+            if !STOPWORDS.contains(&token.as_str()) {
+                *counts.entry(token).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut terms: Vec<(String, usize)> = counts.into_iter().collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.truncate(limit);
+
+    Ok(terms)
+}
+
+#[derive(Serialize)]
+pub struct JournalStats {
+    entry_count: usize,
+    word_count: usize,
+    average_words: f64,
+    /// Dates of the earliest and latest entries as YYYY-MM-DD; None when no entry has a usable date
+    earliest_date: Option<String>,
+    latest_date: Option<String>,
+    tag_count: usize,
+}
+
+/// Totals across the whole journal for the dashboard. Entries whose date doesn't
+/// parse still count towards the totals but not towards the date range.
+#[tauri::command]
+pub fn journal_stats() -> Result<JournalStats, String> {
+    let mut entry_count = 0;
+    let mut word_count = 0;
+    let mut dates = Vec::new();
+    let mut tags = HashSet::new();
+
+    for (_, content) in read_journal_files()? {
+        entry_count += 1;
+        word_count += count_words(frontmatter::body(&content));
+
+        let fields = frontmatter::parse(&content);
+        if let Some(date) = frontmatter::get_str(&fields, "date")
+            .as_deref()
+            .and_then(parse_entry_date)
+        {
+            dates.push(date);
+        }
+        tags.extend(
+            parse_entry_tags(&content)
+                .into_iter()
+                .map(|t| t.to_lowercase()),
+        );
+    }
+
+    let format_date = |date: &NaiveDate| date.format("%Y-%m-%d").to_string();
+
+    Ok(JournalStats {
+        entry_count,
+        word_count,
+        average_words: if entry_count == 0 {
+            0.0
+        } else {
+            word_count as f64 / entry_count as f64
+        },
+        earliest_date: dates.iter().min().map(format_date),
+        latest_date: dates.iter().max().map(format_date),
+        tag_count: tags.len(),
+    })
+}
+
+/// The date of each entry, one per entry; entries without a usable date are skipped
+fn entry_dates() -> Result<Vec<NaiveDate>, String> {
+    Ok(read_journal_files()?
+        .into_iter()
+        .filter_map(|(_, content)| {
+            let date = frontmatter::get_str(&frontmatter::parse(&content), "date")?;
+            parse_entry_date(&date)
+        })
+        .collect())
+}
+
+#[derive(Serialize)]
+pub struct StreakInfo {
+    current: u32,
+    longest: u32,
+}
+
+/// Current and longest runs of consecutive days in dates. The current streak may
+/// end yesterday, so it isn't broken before today's entry is written.
+fn compute_streaks(dates: &BTreeSet<NaiveDate>, today: NaiveDate) -> StreakInfo {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &date in dates {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(date) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(date);
+    }
+
+    let yesterday = today.pred_opt();
+    let current = match previous {
+        Some(last) if last == today || Some(last) == yesterday => run,
+        _ => 0,
+    };
+
+    StreakInfo { current, longest }
+}
+
+/// Consecutive-day writing streaks, counting each day with entries once
+#[tauri::command]
+pub fn writing_streaks() -> Result<StreakInfo, String> {
+    let dates: BTreeSet<NaiveDate> = entry_dates()?.into_iter().collect();
+    Ok(compute_streaks(&dates, Local::now().date_naive()))
+}
+
+#[derive(Serialize)]
+pub struct DayCount {
+    /// YYYY-MM-DD
+    date: String,
+    count: usize,
+}
+
+fn parse_range_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+/// Entries written on each day from start to end inclusive (YYYY-MM-DD), for a
+/// contribution calendar. Days without entries are included with a count of 0.
+#[tauri::command]
+pub fn entries_per_day(start: String, end: String) -> Result<Vec<DayCount>, String> {
+    let start = parse_range_date(&start)?;
+    let end = parse_range_date(&end)?;
+    if end < start {
+        return Err("End date is before start date".to_string());
+    }
+
+    let mut counts: HashMap<NaiveDate, usize> = HashMap::new();
+    for date in entry_dates()? {
+        *counts.entry(date).or_insert(0) += 1;
+    }
+
+    Ok(start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .map(|date| DayCount {
+            date: date.format("%Y-%m-%d").to_string(),
+            count: counts.get(&date).copied().unwrap_or(0),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, TestJournal};
+
+    const SAMPLE_BODY: &str =
+        "# My Day\n\nWent to the **park** today.\n\n## Later\n- read a book\n";
+
+    #[test]
+    fn count_words_skips_markdown_syntax() {
+        assert_eq!(count_words(SAMPLE_BODY), 11);
+        assert_eq!(count_words("  \n# \n- "), 0);
+    }
+
+    #[test]
+    fn entry_stats_ignore_frontmatter() {
+        let journal = TestJournal::new();
+        journal.write(
+            "day.md",
+            &entry(
+                "A walk in the park",
+                "March 1, 2024",
+                &["outdoors"],
+                SAMPLE_BODY,
+            ),
+        );
+
+        let stats = entry_stats("day.md".into(), None).unwrap();
+
+        assert_eq!(stats.word_count, 11);
+        assert_eq!(stats.char_count, 51);
+        assert_eq!(stats.reading_time_minutes, 1);
+    }
+
+    #[test]
+    fn journal_stats_add_up_three_entries() {
+        let journal = TestJournal::new();
+        journal.write("a.md", &entry("A", "March 1, 2024", &["Work"], SAMPLE_BODY));
+        journal.write(
+            "b.md",
+            &entry("B", "January 15, 2023", &["work", "books"], "Two words."),
+        );
+        journal.write(
+            "c.md",
+            &entry("C", "someday", &[], "Five words in this body."),
+        );
+
+        let stats = journal_stats().unwrap();
+
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.word_count, 18);
+        assert_eq!(stats.average_words, 6.0);
+        assert_eq!(stats.earliest_date.as_deref(), Some("2023-01-15"));
+        assert_eq!(stats.latest_date.as_deref(), Some("2024-03-01"));
+        assert_eq!(stats.tag_count, 2);
+    }
+
+    #[test]
+    fn streaks_across_a_gap() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let dates: BTreeSet<NaiveDate> = [1, 2, 3, 4, 7, 8].into_iter().map(day).collect();
+        let streaks = |today| {
+            let info = compute_streaks(&dates, today);
+            (info.current, info.longest)
+        };
+
+        assert_eq!(streaks(day(8)), (2, 4));
+        // Not written yet today: the streak still stands
+        assert_eq!(streaks(day(9)), (2, 4));
+        assert_eq!(streaks(day(10)), (0, 4));
+
+        let none = compute_streaks(&BTreeSet::new(), day(10));
+        assert_eq!((none.current, none.longest), (0, 0));
+    }
+
+    #[test]
+    fn same_day_entries_count_once() {
+        let journal = TestJournal::new();
+        let today = Local::now().date_naive();
+        let date = today.format(&crate::entry_date_format()).to_string();
+        journal.write("a.md", &entry("A", &date, &[], "Morning."));
+        journal.write("b.md", &entry("B", &date, &[], "Evening."));
+        journal.write("c.md", &entry("C", "not a date", &[], "Skipped."));
+
+        let streaks = writing_streaks().unwrap();
+        assert_eq!((streaks.current, streaks.longest), (1, 1));
+    }
+
+    #[test]
+    fn entries_per_day_fills_empty_days() {
+        let journal = TestJournal::new();
+        journal.write("a.md", &entry("A", "March 1, 2024", &[], ""));
+        journal.write("b.md", &entry("B", "March 1, 2024", &[], ""));
+        journal.write("c.md", &entry("C", "March 3, 2024", &[], ""));
+        journal.write("d.md", &entry("D", "March 9, 2024", &[], ""));
+
+        let days: Vec<(String, usize)> = entries_per_day("2024-03-01".into(), "2024-03-03".into())
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.date, d.count))
+            .collect();
+
+        assert_eq!(
+            days,
+            [
+                ("2024-03-01".into(), 2),
+                ("2024-03-02".into(), 0),
+                ("2024-03-03".into(), 1),
+            ]
+        );
+        assert!(entries_per_day("2024-03-03".into(), "2024-03-01".into()).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Local};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::frontmatter;
+use crate::settings::{load_settings, update_settings};
+use crate::{
+    entry_date_format, get_journal_dir, notebook_dir, parse_entry_date, unique_filename,
+    write_atomic,
+};
+
+pub const TEMPLATES_DIR: &str = "templates";
+
+pub fn get_templates_dir() -> PathBuf {
+    get_journal_dir().join(TEMPLATES_DIR)
+}
+
+fn template_path(name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid template name: {}", name));
+    }
+
+    let filename = if name.ends_with(".md") {
+        name.to_string()
+    } else {
+        format!("{}.md", name)
+    };
+
+    Ok(get_templates_dir().join(filename))
+}
+
+/// Substitute {{date}} and {{time}} placeholders
+fn substitute_placeholders(template: &str, now: &DateTime<Local>) -> String {
+    template
+        .replace("{{date}}", &now.format(&entry_date_format()).to_string())
+        .replace("{{time}}", &now.format("%-I:%M %p").to_string())
+}
+
+/// Render a template for a new entry dated now. Templates without frontmatter get
+/// the standard header so the entry still lists with a date.
+pub fn render_template(name: &str, now: &DateTime<Local>) -> Result<String, String> {
+    let path = template_path(name)?;
+    if !path.exists() {
+        return Err(format!("Template not found: {}", name.trim()));
+    }
+
+    let template = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let rendered = substitute_placeholders(&template, now);
+    let date_string = now.format(&entry_date_format()).to_string();
+
+    if !frontmatter::has_frontmatter(&rendered) {
+        return Ok(format!(
+            "---\ntitle: \ndate: {}\n---\n\n{}",
+            date_string, rendered
+        ));
+    }
+
+    // A template with no date, or one that doesn't parse, still produces an entry dated today
+    let fields = frontmatter::parse(&rendered);
+    if frontmatter::get_str(&fields, "date")
+        .and_then(|d| parse_entry_date(&d))
+        .is_none()
+    {
+        return frontmatter::set_field(&rendered, "date", &date_string);
+    }
+
+    Ok(rendered)
+}
+
+/// Names of the templates in the templates folder, without the .md extension
+#[tauri::command]
+pub fn list_templates() -> Result<Vec<String>, String> {
+    let templates_dir = get_templates_dir();
+    if !templates_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = fs::read_dir(&templates_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("md"))
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(String::from))
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+
+    names.sort();
+    Ok(names)
+}
+
+/// Create a new entry dated today from a template. Returns the new filename.
+#[tauri::command]
+pub fn create_entry_from_template(
+    template: String,
+    notebook: Option<String>,
+) -> Result<String, String> {
+    let now = Local::now();
+    let date_string = now.format(&entry_date_format()).to_string();
+
+    let content = render_template(&template, &now)?;
+
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
+
+    let filename = unique_filename(&journal_dir, &date_string);
+    write_atomic(&journal_dir.join(&filename), &content)?;
+
+    Ok(filename)
+}
+
+/// Settings key for a notebook; the top-level journal uses the empty key
+fn notebook_key(notebook: Option<&str>) -> String {
+    notebook.map(|n| n.trim().to_string()).unwrap_or_default()
+}
+
+/// Default template configured for a notebook, if any
+pub fn default_template_for(notebook: Option<&str>) -> Option<String> {
+    load_settings()
+        .default_templates
+        .get(&notebook_key(notebook))
+        .cloned()
+}
+
+#[tauri::command]
+pub fn get_notebook_template(notebook: Option<String>) -> Result<Option<String>, String> {
+    Ok(default_template_for(notebook.as_deref()))
+}
+
+/// Set (or with None, clear) the template new entries in a notebook start from
+#[tauri::command]
+pub fn set_notebook_template(
+    notebook: Option<String>,
+    template: Option<String>,
+) -> Result<(), String> {
+    let key = notebook_key(notebook.as_deref());
+
+    if let Some(template) = &template {
+        if !template_path(template)?.exists() {
+            return Err(format!("Template not found: {}", template.trim()));
+        }
+    }
+
+    update_settings(|s| match template {
+        Some(template) => {
+            s.default_templates.insert(key, template.trim().to_string());
+        }
+        None => {
+            s.default_templates.remove(&key);
+        }
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::settings::{save_settings, set_test_config_path, Settings};
+
+/// A journal, models folder and config file in a temporary directory. While it is
+/// alive, code on the creating thread reads and writes these instead of the user's.
+pub struct TestJournal {
+    dir: tempfile::TempDir,
+}
+
+impl TestJournal {
+    pub fn new() -> Self {
+        Self::with_settings(Settings::default())
+    }
+
+    /// A test journal starting from the given settings; its folders always win
+    pub fn with_settings(mut settings: Settings) -> Self {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let journal = dir.path().join("journal");
+        fs::create_dir_all(&journal).expect("Failed to create journal dir");
+
+        settings.journal_dir = Some(journal.to_string_lossy().into_owned());
+        settings.models_dir = Some(dir.path().join("models").to_string_lossy().into_owned());
+
+        set_test_config_path(Some(dir.path().join("config.json")));
+        save_settings(&settings).expect("Failed to write test settings");
+
+        Self { dir }
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.dir.path().join("journal")
+    }
+
+    /// Write a file relative to the journal root, creating folders as needed
+    pub fn write(&self, relative: &str, content: &str) -> PathBuf {
+        let path = self.root().join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create fixture dir");
+        }
+        fs::write(&path, content).expect("Failed to write fixture");
+        path
+    }
+
+    pub fn read(&self, relative: &str) -> String {
+        fs::read_to_string(self.root().join(relative)).expect("Failed to read fixture")
+    }
+
+    /// A path outside the journal, for files being imported or exported
+    pub fn outside(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+}
+
+impl Drop for TestJournal {
+    fn drop(&mut self) {
+        set_test_config_path(None);
+    }
+}
+
+/// Entry content with the given title, date and tags in its frontmatter
+pub fn entry(title: &str, date: &str, tags: &[&str], body: &str) -> String {
+    let tags = if tags.is_empty() {
+        String::new()
+    } else {
+        format!("tags: [{}]\n", tags.join(", "))
+    };
+    format!(
+        "---\ntitle: {}\ndate: {}\n{}---\n\n{}",
+        title, date, tags, body
+    )
+}
+
+/// Set a file's modification time
+pub fn set_mtime(path: &Path, time: std::time::SystemTime) {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(time))
+        .expect("Failed to set mtime");
+}
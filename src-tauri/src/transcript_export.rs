@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::export::ensure_parent_exists;
+use crate::transcription::Segment;
+
+/// Bumped whenever the exported JSON layout changes incompatibly
+pub const SEGMENTS_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SegmentsExport<'a> {
+    schema_version: u32,
+    segments: &'a [Segment],
+}
+
+/// Serialize segments as versioned JSON, optionally also writing it to out_path
+pub fn segments_to_json(segments: &[Segment], out_path: Option<&str>) -> Result<String, String> {
+    let export = SegmentsExport {
+        schema_version: SEGMENTS_SCHEMA_VERSION,
+        segments,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+
+    if let Some(out_path) = out_path {
+        let out_path = Path::new(out_path);
+        ensure_parent_exists(out_path)?;
+        fs::write(out_path, &json).map_err(|e| format!("Failed to write JSON: {}", e))?;
+    }
+
+    Ok(json)
+}
+
+/// `HH:MM:SS<sep>mmm`; hours keep counting past 99 rather than wrapping
+fn timecode(ms: i64, separator: char) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// Format segments as SubRip (.srt) subtitles
+#[tauri::command]
+pub fn export_srt(segments: Vec<Segment>) -> String {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                timecode(segment.start_ms, ','),
+                timecode(segment.end_ms, ','),
+                segment.text.trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format segments as WebVTT (.vtt) subtitles
+#[tauri::command]
+pub fn export_vtt(segments: Vec<Segment>) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+
+    for segment in &segments {
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            timecode(segment.start_ms, '.'),
+            timecode(segment.end_ms, '.'),
+            segment.text.trim()
+        ));
+    }
+
+    vtt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: i64, end_ms: i64, text: &str) -> Segment {
+        Segment {
+            index: 0,
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            no_speech_prob: None,
+            confidence: 1.0,
+            low_confidence: false,
+            tokens: None,
+        }
+    }
+
+    #[test]
+    fn timecodes_past_an_hour() {
+        assert_eq!(timecode(3_661_500, ','), "01:01:01,500");
+        assert_eq!(timecode(3_661_500, '.'), "01:01:01.500");
+        assert_eq!(timecode(0, ','), "00:00:00,000");
+        assert_eq!(timecode(-20, ','), "00:00:00,000");
+        assert_eq!(timecode(360_000_000, ','), "100:00:00,000");
+    }
+
+    #[test]
+    fn srt_and_vtt_layout() {
+        let segments = vec![
+            segment(0, 1500, " Hello."),
+            segment(3_600_000, 3_661_500, " An hour in."),
+        ];
+
+        assert_eq!(
+            export_srt(segments.clone()),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n\
+             2\n01:00:00,000 --> 01:01:01,500\nAn hour in.\n"
+        );
+        assert_eq!(
+            export_vtt(segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello.\n\n\
+             01:00:00.000 --> 01:01:01.500\nAn hour in.\n"
+        );
+    }
+}
@@ -1,48 +1,300 @@
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use thread_priority::{set_current_thread_priority, ThreadPriority};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
-use crate::whisper_model::{get_model_path, is_model_downloaded};
+use crate::settings::{load_settings, update_settings, TranscriptionPriority};
+use crate::whisper_model::{get_active_model, get_model_path, is_model_downloaded, WhisperModel};
+
+/// A loaded Whisper context and the model it was loaded from
+struct LoadedModel {
+    model: WhisperModel,
+    ctx: WhisperContext,
+}
 
 // Global Whisper context - expensive to create, so we reuse it
-static WHISPER_CTX: Lazy<Mutex<Option<WhisperContext>>> = Lazy::new(|| Mutex::new(None));
+static WHISPER_CTX: Lazy<Mutex<Option<LoadedModel>>> = Lazy::new(|| Mutex::new(None));
+
+// Set by cancel_transcription and polled by Whisper's abort callback. Kept outside
+// WHISPER_CTX so cancelling never waits on the lock the running transcription holds.
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Error returned by a transcription stopped with cancel_transcription
+pub const CANCELLED: &str = "cancelled";
+
+/// Polled by Whisper between steps; true stops the run in progress
+fn should_abort() -> bool {
+    CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+// Built with a GPU backend (the `metal` or `cuda` feature)
+const GPU_BUILD: bool = cfg!(any(feature = "metal", feature = "cuda"));
 
-/// Initialize or get the Whisper context
-fn ensure_context_initialized() -> Result<(), String> {
-    let mut ctx_guard = WHISPER_CTX.lock().map_err(|e| format!("Lock error: {}", e))?;
+// Set once creating a GPU context has failed, so later loads go straight to the CPU
+static GPU_FAILED: AtomicBool = AtomicBool::new(false);
 
-    if ctx_guard.is_none() {
-        if !is_model_downloaded() {
+/// Load a model on the GPU when built with a GPU backend, falling back to the CPU
+/// if that fails (e.g. no supported device or driver)
+fn load_context(model_path: &str) -> Result<WhisperContext, String> {
+    if GPU_BUILD && !GPU_FAILED.load(Ordering::SeqCst) {
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(true);
+
+        match WhisperContext::new_with_params(model_path, params) {
+            Ok(ctx) => return Ok(ctx),
+            Err(e) => {
+                eprintln!(
+                    "Failed to load Whisper model on the GPU, using the CPU: {}",
+                    e
+                );
+                GPU_FAILED.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    let mut params = WhisperContextParameters::default();
+    params.use_gpu(false);
+    WhisperContext::new_with_params(model_path, params)
+        .map_err(|e| format!("Failed to load Whisper model: {}", e))
+}
+
+/// Initialize or get the Whisper context, reloading it if the active model changed.
+/// Takes the caller's WHISPER_CTX guard so the model it checks is the one it then uses.
+fn ensure_context_initialized(ctx_guard: &mut Option<LoadedModel>) -> Result<&LoadedModel, String> {
+    let active_model = get_active_model();
+
+    let is_current = ctx_guard
+        .as_ref()
+        .is_some_and(|loaded| loaded.model == active_model);
+
+    if !is_current {
+        // Release the old model before loading the new one
+        *ctx_guard = None;
+
+        if !is_model_downloaded(active_model) {
             return Err("Whisper model not downloaded. Please download it first.".to_string());
         }
 
-        let model_path = get_model_path();
-        let model_path_str = model_path
-            .to_str()
-            .ok_or("Invalid model path encoding")?;
+        let model_path = get_model_path(active_model);
+        let model_path_str = model_path.to_str().ok_or("Invalid model path encoding")?;
 
-        let ctx = WhisperContext::new_with_params(model_path_str, WhisperContextParameters::default())
-            .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+        let ctx = load_context(model_path_str)?;
 
-        *ctx_guard = Some(ctx);
+        *ctx_guard = Some(LoadedModel {
+            model: active_model,
+            ctx,
+        });
     }
 
-    Ok(())
+    ctx_guard
+        .as_ref()
+        .ok_or_else(|| "Whisper context not initialized".to_string())
 }
 
-/// Transcribe audio samples (must be 16kHz mono f32)
-pub fn transcribe_audio(samples: &[f32]) -> Result<String, String> {
+/// How Whisper picks tokens: greedy is fastest, beam search more accurate on hard audio
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecodingStrategy {
+    Greedy { best_of: i32 },
+    BeamSearch { beam_size: i32, patience: f32 },
+}
+
+impl Default for DecodingStrategy {
+    fn default() -> Self {
+        DecodingStrategy::Greedy { best_of: 1 }
+    }
+}
+
+impl DecodingStrategy {
+    fn validate(&self) -> Result<(), String> {
+        match *self {
+            DecodingStrategy::Greedy { best_of } if best_of < 1 => {
+                Err("best_of must be at least 1".to_string())
+            }
+            DecodingStrategy::BeamSearch { beam_size, .. } if beam_size < 1 => {
+                Err("Beam size must be at least 1".to_string())
+            }
+            DecodingStrategy::BeamSearch { patience, .. } if !patience.is_finite() => {
+                Err("Beam search patience must be a number".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn to_sampling_strategy(self) -> SamplingStrategy {
+        match self {
+            DecodingStrategy::Greedy { best_of } => SamplingStrategy::Greedy { best_of },
+            DecodingStrategy::BeamSearch {
+                beam_size,
+                patience,
+            } => SamplingStrategy::BeamSearch {
+                beam_size,
+                patience,
+            },
+        }
+    }
+}
+
+/// Options for a single transcription run
+#[derive(Clone)]
+pub struct TranscribeOptions {
+    /// Collect per-token timings and probabilities
+    pub token_timestamps: bool,
+    /// Spoken language code such as "en" or "es"; None lets Whisper detect it.
+    /// Anything but English needs a multilingual (non-".en") model.
+    pub language: Option<String>,
+    /// Translate the speech into English text; also needs a multilingual model
+    pub translate: bool,
+    /// Worker threads; None uses the saved setting or a count based on the CPU cores
+    pub n_threads: Option<usize>,
+    /// Called with the percentage done (0-100) each time it changes
+    pub on_progress: Option<Arc<dyn Fn(u8) + Send + Sync>>,
+    /// Text to prime the decoder with, e.g. names and jargon to spell the way given
+    pub initial_prompt: Option<String>,
+    pub strategy: DecodingStrategy,
+}
+
+impl Default for TranscribeOptions {
+    fn default() -> Self {
+        Self {
+            token_timestamps: false,
+            language: Some("en".to_string()),
+            translate: false,
+            n_threads: None,
+            on_progress: None,
+            initial_prompt: None,
+            strategy: DecodingStrategy::default(),
+        }
+    }
+}
+
+// Whisper speeds up little past this many threads
+const MAX_DEFAULT_THREADS: usize = 8;
+
+/// Threads to use on a machine with the given number of cores
+fn default_thread_count(cores: usize) -> usize {
+    cores.clamp(1, MAX_DEFAULT_THREADS)
+}
+
+/// The requested thread count, else the saved setting, else one based on the CPU cores
+fn resolve_thread_count(requested: Option<usize>) -> usize {
+    requested
+        .or(load_settings().transcription_threads)
+        .unwrap_or_else(|| {
+            let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+            default_thread_count(cores)
+        })
+        .max(1)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TokenTiming {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub probability: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Segment {
+    #[serde(default)]
+    pub index: usize,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    /// whisper-rs 0.12 doesn't expose the no-speech probability, so this is null for now
+    pub no_speech_prob: Option<f32>,
+    /// Mean probability (0-1) of the segment's text tokens
+    #[serde(default)]
+    pub confidence: f32,
+    /// Confidence fell below the requested minimum; worth reviewing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub low_confidence: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<TokenTiming>>,
+}
+
+/// Transcribe audio samples (must be 16kHz mono f32) into timed segments
+pub fn transcribe_segments(
+    samples: &[f32],
+    options: &TranscribeOptions,
+) -> Result<Vec<Segment>, String> {
+    match load_settings().transcription_priority {
+        TranscriptionPriority::Normal => run_transcription(samples, options),
+        TranscriptionPriority::Low => {
+            // Whisper's worker threads are spawned from the calling thread and inherit
+            // its priority, so run the whole transcription on a lowered thread
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        if let Err(e) = set_current_thread_priority(ThreadPriority::Min) {
+                            eprintln!("Failed to lower transcription priority: {:?}", e);
+                        }
+                        run_transcription(samples, options)
+                    })
+                    .join()
+                    .map_err(|_| "Transcription thread panicked".to_string())?
+            })
+        }
+    }
+}
+
+/// Why model can't run with options, if it is English-only and they ask it to
+/// detect, transcribe or translate another language
+fn english_only_error(model: WhisperModel, options: &TranscribeOptions) -> Option<String> {
+    if !model.is_english_only() || (!options.translate && options.language.as_deref() == Some("en"))
+    {
+        return None;
+    }
+
+    Some(match &options.language {
+        _ if options.translate => format!(
+            "The {} model only supports English. Switch to a multilingual model to translate.",
+            model
+        ),
+        Some(language) => format!(
+            "The {} model only supports English. Switch to a multilingual model to transcribe \"{}\".",
+            model, language
+        ),
+        None => format!(
+            "The {} model only supports English. Switch to a multilingual model to detect the language.",
+            model
+        ),
+    })
+}
+
+/// The prompt to prime the decoder with; a blank one is the same as none
+fn initial_prompt(options: &TranscribeOptions) -> Option<&str> {
+    options
+        .initial_prompt
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+}
+
+fn run_transcription(samples: &[f32], options: &TranscribeOptions) -> Result<Vec<Segment>, String> {
     if samples.is_empty() {
         return Err("No audio samples provided".to_string());
     }
 
-    // Ensure context is initialized
-    ensure_context_initialized()?;
+    options.strategy.validate()?;
 
-    let ctx_guard = WHISPER_CTX.lock().map_err(|e| format!("Lock error: {}", e))?;
-    let ctx = ctx_guard
-        .as_ref()
-        .ok_or("Whisper context not initialized")?;
+    // Held until the transcription finishes so a model switch can't slip in between
+    let mut ctx_guard = WHISPER_CTX
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let loaded = ensure_context_initialized(&mut ctx_guard)?;
+
+    if let Some(e) = english_only_error(loaded.model, options) {
+        return Err(e);
+    }
+
+    let ctx = &loaded.ctx;
+
+    // A cancel aimed at an earlier run shouldn't stop this one
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
 
     // Create state for this transcription
     let mut state = ctx
@@ -50,41 +302,205 @@ pub fn transcribe_audio(samples: &[f32]) -> Result<String, String> {
         .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
 
     // Configure transcription parameters
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    let mut params = FullParams::new(options.strategy.to_sampling_strategy());
 
-    // Optimize for speed and English
-    params.set_n_threads(4);
-    params.set_language(Some("en"));
-    params.set_translate(false);
+    // Optimize for speed
+    params.set_n_threads(resolve_thread_count(options.n_threads) as i32);
+    params.set_language(options.language.as_deref());
+    params.set_translate(options.translate);
     params.set_no_context(true);
     params.set_single_segment(false);
     params.set_print_special(false);
     params.set_print_progress(false);
     params.set_print_realtime(false);
     params.set_print_timestamps(false);
+    params.set_token_timestamps(options.token_timestamps);
+
+    if let Some(prompt) = initial_prompt(options) {
+        params.set_initial_prompt(prompt);
+    }
+
+    if let Some(on_progress) = options.on_progress.clone() {
+        // Whisper can report the same percentage several times in a row
+        let mut last_percent = None;
+        params.set_progress_callback_safe(move |progress: i32| {
+            let percent = progress.clamp(0, 100) as u8;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                on_progress(percent);
+            }
+        });
+    }
+
+    params.set_abort_callback_safe(should_abort);
 
     // Run transcription
-    state
-        .full(params, samples)
-        .map_err(|e| format!("Transcription failed: {}", e))?;
+    let result = state.full(params, samples);
+    if CANCEL_REQUESTED.swap(false, Ordering::SeqCst) {
+        return Err(CANCELLED.to_string());
+    }
+    result.map_err(|e| format!("Transcription failed: {}", e))?;
 
     // Collect all segments
     let num_segments = state
         .full_n_segments()
         .map_err(|e| format!("Failed to get segment count: {}", e))?;
 
-    let mut transcript = String::new();
+    let mut segments = Vec::new();
 
     for i in 0..num_segments {
-        if let Ok(segment_text) = state.full_get_segment_text(i) {
-            if !transcript.is_empty() && !segment_text.starts_with(' ') {
-                transcript.push(' ');
+        let text = match state.full_get_segment_text(i) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+
+        // Whisper timestamps are in centiseconds
+        let start_ms = state.full_get_segment_t0(i).unwrap_or(0) * 10;
+        let end_ms = state.full_get_segment_t1(i).unwrap_or(0) * 10;
+
+        let num_tokens = state.full_n_tokens(i).unwrap_or(0);
+        let mut tokens = Vec::new();
+        let mut probability_sum = 0.0f32;
+
+        for t in 0..num_tokens {
+            let (Ok(token_text), Ok(data)) = (
+                state.full_get_token_text(i, t),
+                state.full_get_token_data(i, t),
+            ) else {
+                continue;
+            };
+
+            // Skip special tokens like [_BEG_] and <|endoftext|>
+            if token_text.starts_with("[_") || token_text.starts_with("<|") {
+                continue;
             }
-            transcript.push_str(&segment_text);
+
+            probability_sum += data.p;
+            tokens.push(TokenTiming {
+                text: token_text,
+                start_ms: data.t0 * 10,
+                end_ms: data.t1 * 10,
+                probability: data.p,
+            });
         }
+
+        let confidence = if tokens.is_empty() {
+            0.0
+        } else {
+            (probability_sum / tokens.len() as f32).clamp(0.0, 1.0)
+        };
+
+        segments.push(Segment {
+            index: segments.len(),
+            start_ms,
+            end_ms,
+            text,
+            no_speech_prob: None,
+            confidence,
+            low_confidence: false,
+            tokens: options.token_timestamps.then_some(tokens),
+        });
     }
 
-    let result = transcript.trim().to_string();
+    Ok(segments)
+}
+
+/// Detect the spoken language of audio samples (must be 16kHz mono f32), returning
+/// its code such as "es". Runs only Whisper's detection pass on the first 30 seconds,
+/// not a transcription. Needs a multilingual model.
+pub fn detect_language(samples: &[f32]) -> Result<String, String> {
+    if samples.is_empty() {
+        return Err("No audio samples provided".to_string());
+    }
+
+    let mut ctx_guard = WHISPER_CTX
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let loaded = ensure_context_initialized(&mut ctx_guard)?;
+
+    if let Some(e) = english_only_error(loaded.model, &language_options(None)) {
+        return Err(e);
+    }
+
+    let mut state = loaded
+        .ctx
+        .create_state()
+        .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+    let threads = resolve_thread_count(None);
+    state
+        .pcm_to_mel(samples, threads)
+        .map_err(|e| format!("Failed to compute spectrogram: {}", e))?;
+    let probabilities = state
+        .lang_detect(0, threads)
+        .map_err(|e| format!("Language detection failed: {}", e))?;
+
+    let best = probabilities
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(id, _)| id as i32)
+        .ok_or("Language detection returned no result")?;
+
+    whisper_rs::get_lang_str(best)
+        .map(str::to_string)
+        .ok_or_else(|| format!("Unknown language id {}", best))
+}
+
+/// Join segment texts into a single transcript
+pub fn join_segments(segments: &[Segment]) -> String {
+    let mut transcript = String::new();
+
+    for segment in segments {
+        if !transcript.is_empty() && !segment.text.starts_with(' ') {
+            transcript.push(' ');
+        }
+        transcript.push_str(&segment.text);
+    }
+
+    transcript.trim().to_string()
+}
+
+/// Transcribe audio samples (must be 16kHz mono f32) as English
+pub fn transcribe_audio(samples: &[f32]) -> Result<String, String> {
+    transcribe_audio_with_language(samples, Some("en"))
+}
+
+/// Transcribe audio samples (must be 16kHz mono f32) in the given language, or
+/// with the language detected when None
+pub fn transcribe_audio_with_language(
+    samples: &[f32],
+    language: Option<&str>,
+) -> Result<String, String> {
+    transcribe_text(samples, &language_options(language))
+}
+
+/// Default options with the spoken language set, None to detect it
+fn language_options(language: Option<&str>) -> TranscribeOptions {
+    TranscribeOptions {
+        language: language.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+/// Translate speech in audio samples (must be 16kHz mono f32) into English text.
+/// language is the spoken language, or None to detect it.
+pub fn translate_audio(samples: &[f32], language: Option<&str>) -> Result<String, String> {
+    transcribe_text(samples, &translate_options(language))
+}
+
+/// Options translating speech in the given language, None to detect it, into English
+fn translate_options(language: Option<&str>) -> TranscribeOptions {
+    TranscribeOptions {
+        translate: true,
+        ..language_options(language)
+    }
+}
+
+/// Transcribe with the given options into a single transcript
+pub fn transcribe_text(samples: &[f32], options: &TranscribeOptions) -> Result<String, String> {
+    let segments = transcribe_segments(samples, options)?;
+    let result = join_segments(&segments);
 
     if result.is_empty() {
         return Err("No speech detected in the audio".to_string());
@@ -93,10 +509,296 @@ pub fn transcribe_audio(samples: &[f32]) -> Result<String, String> {
     Ok(result)
 }
 
+/// Transcribe audio samples (must be 16kHz mono f32) into segments with start and
+/// end times and per-word timings, for captions and click-to-seek
+pub fn transcribe_audio_detailed(samples: &[f32]) -> Result<Vec<Segment>, String> {
+    let options = TranscribeOptions {
+        token_timestamps: true,
+        ..Default::default()
+    };
+    transcribe_segments(samples, &options)
+}
+
+/// Mark segments whose confidence is below min_confidence for review
+pub fn mark_low_confidence(segments: &mut [Segment], min_confidence: f32) {
+    for segment in segments {
+        segment.low_confidence = segment.confidence < min_confidence;
+    }
+}
+
+/// The model currently loaded into the Whisper context, if any
+pub fn loaded_model() -> Option<WhisperModel> {
+    WHISPER_CTX.lock().ok()?.as_ref().map(|loaded| loaded.model)
+}
+
 /// Unload the Whisper model to free memory
-#[allow(dead_code)]
 pub fn unload_model() {
     if let Ok(mut ctx_guard) = WHISPER_CTX.lock() {
         *ctx_guard = None;
     }
 }
+
+/// Unload the model unless a transcription currently holds it.
+/// A busy context is left alone; it gets swapped on the next transcription.
+pub fn unload_model_if_idle() {
+    if let Ok(mut ctx_guard) = WHISPER_CTX.try_lock() {
+        *ctx_guard = None;
+    }
+}
+
+/// Whether transcription can run on the GPU: built with a GPU backend and not
+/// (yet) fallen back to the CPU
+#[tauri::command]
+pub fn gpu_available() -> bool {
+    GPU_BUILD && !GPU_FAILED.load(Ordering::SeqCst)
+}
+
+/// Stop the transcription in progress; it returns the "cancelled" error
+#[tauri::command]
+pub fn cancel_transcription() {
+    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+#[tauri::command]
+pub fn get_transcription_priority() -> Result<TranscriptionPriority, String> {
+    Ok(load_settings().transcription_priority)
+}
+
+#[tauri::command]
+pub fn set_transcription_priority(priority: TranscriptionPriority) -> Result<(), String> {
+    update_settings(|s| s.transcription_priority = priority)?;
+    Ok(())
+}
+
+/// The saved thread count, or None when it's picked automatically
+#[tauri::command]
+pub fn get_transcription_threads() -> Result<Option<usize>, String> {
+    Ok(load_settings().transcription_threads)
+}
+
+/// Save the thread count for transcriptions; None goes back to picking it automatically
+#[tauri::command]
+pub fn set_transcription_threads(threads: Option<usize>) -> Result<(), String> {
+    if threads == Some(0) {
+        return Err("Thread count must be at least 1".to_string());
+    }
+
+    update_settings(|s| s.transcription_threads = threads)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::test_support::TestJournal;
+
+    /// Speech for the tests that run the installed model: the audio file named by
+    /// FLOW_TEST_SPEECH, converted for Whisper
+    fn speech_clip() -> Vec<f32> {
+        let path = std::env::var("FLOW_TEST_SPEECH")
+            .expect("Set FLOW_TEST_SPEECH to an audio file of speech");
+        crate::audio_import::convert_to_whisper_format(std::path::Path::new(&path)).unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a downloaded model and FLOW_TEST_SPEECH"]
+    fn detailed_segments_come_back_in_order() {
+        let segments = transcribe_audio_detailed(&speech_clip()).unwrap();
+        assert!(!segments.is_empty());
+
+        for pair in segments.windows(2) {
+            assert!(pair[0].start_ms < pair[1].start_ms);
+        }
+        for segment in &segments {
+            assert!(segment.start_ms <= segment.end_ms);
+            let tokens = segment.tokens.as_ref().unwrap();
+            assert!(tokens.windows(2).all(|t| t[0].start_ms <= t[1].start_ms));
+        }
+    }
+
+    #[test]
+    fn english_keeps_the_previous_behaviour() {
+        // What transcribe_audio hardcoded before the language option existed
+        let options = language_options(Some("en"));
+        assert_eq!(options.language.as_deref(), Some("en"));
+        assert!(!options.translate);
+        assert!(!options.token_timestamps);
+        assert!(options.n_threads.is_none());
+        assert!(options.initial_prompt.is_none());
+        assert_eq!(options.strategy, DecodingStrategy::Greedy { best_of: 1 });
+
+        assert_eq!(english_only_error(WhisperModel::BaseEn, &options), None);
+        assert_eq!(english_only_error(WhisperModel::Base, &options), None);
+    }
+
+    #[test]
+    fn other_languages_need_a_multilingual_model() {
+        let spanish = language_options(Some("es"));
+        assert_eq!(english_only_error(WhisperModel::Base, &spanish), None);
+        assert!(english_only_error(WhisperModel::BaseEn, &spanish)
+            .unwrap()
+            .contains("to transcribe \"es\""));
+    }
+
+    #[test]
+    fn translation_is_forwarded_and_guarded() {
+        let options = translate_options(Some("es"));
+        assert!(options.translate);
+        assert_eq!(options.language.as_deref(), Some("es"));
+        assert!(translate_options(None).language.is_none());
+
+        assert_eq!(english_only_error(WhisperModel::Small, &options), None);
+        // Even English speech can't be "translated" by an English-only model
+        assert!(
+            english_only_error(WhisperModel::SmallEn, &translate_options(Some("en")))
+                .unwrap()
+                .contains("to translate")
+        );
+    }
+
+    #[test]
+    fn default_threads_follow_the_cores_up_to_eight() {
+        for (cores, threads) in [(0, 1), (1, 1), (2, 2), (4, 4), (8, 8), (12, 8), (64, 8)] {
+            assert_eq!(default_thread_count(cores), threads, "{} cores", cores);
+        }
+    }
+
+    #[test]
+    fn requested_threads_beat_the_saved_setting() {
+        let _journal = TestJournal::with_settings(Settings {
+            transcription_threads: Some(3),
+            ..Default::default()
+        });
+
+        assert_eq!(resolve_thread_count(None), 3);
+        assert_eq!(resolve_thread_count(Some(6)), 6);
+        assert_eq!(resolve_thread_count(Some(0)), 1);
+    }
+
+    #[test]
+    fn cancelling_makes_the_abort_callback_stop() {
+        assert!(!should_abort());
+
+        // A running transcription holds the context lock; cancelling mustn't wait on it
+        let ctx_guard = WHISPER_CTX.lock().unwrap();
+        std::thread::spawn(cancel_transcription).join().unwrap();
+        assert!(should_abort());
+        drop(ctx_guard);
+
+        CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn blank_prompts_are_left_unset() {
+        let with_prompt = |prompt: Option<&str>| TranscribeOptions {
+            initial_prompt: prompt.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(initial_prompt(&TranscribeOptions::default()), None);
+        assert_eq!(initial_prompt(&with_prompt(Some(""))), None);
+        assert_eq!(initial_prompt(&with_prompt(Some("  \n"))), None);
+        assert_eq!(
+            initial_prompt(&with_prompt(Some(" Flow, Tauri, Whisper "))),
+            Some("Flow, Tauri, Whisper")
+        );
+    }
+
+    #[test]
+    fn strategies_map_to_whisper() {
+        assert!(matches!(
+            DecodingStrategy::default().to_sampling_strategy(),
+            SamplingStrategy::Greedy { best_of: 1 }
+        ));
+
+        let beam: DecodingStrategy =
+            serde_json::from_str(r#"{"kind": "beam_search", "beam_size": 5, "patience": 1.0}"#)
+                .unwrap();
+        assert!(matches!(
+            beam.to_sampling_strategy(),
+            SamplingStrategy::BeamSearch { beam_size: 5, patience } if patience == 1.0
+        ));
+    }
+
+    #[test]
+    fn beam_size_must_be_positive() {
+        let beam = |beam_size| DecodingStrategy::BeamSearch {
+            beam_size,
+            patience: 1.0,
+        };
+
+        assert!(beam(1).validate().is_ok());
+        assert!(beam(0).validate().is_err());
+        assert!(DecodingStrategy::Greedy { best_of: 0 }.validate().is_err());
+    }
+
+    #[test]
+    #[ignore = "needs a downloaded model and FLOW_TEST_SPEECH"]
+    fn confidence_is_a_probability() {
+        let segments = transcribe_audio_detailed(&speech_clip()).unwrap();
+        assert!(!segments.is_empty());
+        assert!(segments.iter().all(|s| (0.0..=1.0).contains(&s.confidence)));
+        // Clear speech shouldn't be a coin toss
+        assert!(segments.iter().any(|s| s.confidence > 0.5));
+    }
+
+    #[test]
+    fn segments_below_the_minimum_are_marked() {
+        let segment = |confidence| Segment {
+            index: 0,
+            start_ms: 0,
+            end_ms: 0,
+            text: String::new(),
+            no_speech_prob: None,
+            confidence,
+            low_confidence: false,
+            tokens: None,
+        };
+        let mut segments = vec![segment(0.2), segment(0.6), segment(0.9)];
+
+        mark_low_confidence(&mut segments, 0.6);
+        let marked: Vec<bool> = segments.iter().map(|s| s.low_confidence).collect();
+        assert_eq!(marked, [true, false, false]);
+    }
+
+    #[test]
+    #[ignore = "needs a downloaded model"]
+    fn cpu_fallback_gives_a_working_context() {
+        let model_path = get_model_path(get_active_model());
+
+        // As if creating the GPU context had already failed on this machine
+        GPU_FAILED.store(true, Ordering::SeqCst);
+        assert!(!gpu_available());
+
+        let ctx = load_context(model_path.to_str().unwrap()).unwrap();
+        let mut state = ctx.create_state().unwrap();
+        let params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        state.full(params, &[0.0; 16000]).unwrap();
+    }
+
+    #[test]
+    fn missing_model_file_is_an_error() {
+        let journal = TestJournal::new();
+        let missing = journal.outside("ggml-missing.bin");
+
+        let err = load_context(missing.to_str().unwrap()).err().unwrap();
+        assert!(err.starts_with("Failed to load Whisper model"));
+    }
+
+    #[test]
+    fn detection_needs_a_multilingual_model() {
+        let detect = language_options(None);
+        assert_eq!(english_only_error(WhisperModel::Base, &detect), None);
+
+        for model in [WhisperModel::TinyEn, WhisperModel::BaseEn] {
+            assert_eq!(
+                english_only_error(model, &detect).unwrap(),
+                format!(
+                    "The {} model only supports English. Switch to a multilingual model to detect the language.",
+                    model
+                )
+            );
+        }
+    }
+}
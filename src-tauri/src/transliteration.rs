@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+
+/// Target scripts that can be produced offline from a transcript
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    /// Hepburn-style romanization of Japanese kana
+    Romaji,
+}
+
+#[derive(Serialize)]
+pub struct ScriptedTranscript {
+    pub text: String,
+    pub transliterated: Option<String>,
+}
+
+// Hiragana syllables with their romaji. Katakana is folded onto hiragana first.
+const KANA_DIGRAPHS: &[(&str, &str)] = &[
+    ("きゃ", "kya"),
+    ("きゅ", "kyu"),
+    ("きょ", "kyo"),
+    ("しゃ", "sha"),
+    ("しゅ", "shu"),
+    ("しょ", "sho"),
+    ("ちゃ", "cha"),
+    ("ちゅ", "chu"),
+    ("ちょ", "cho"),
+    ("にゃ", "nya"),
+    ("にゅ", "nyu"),
+    ("にょ", "nyo"),
+    ("ひゃ", "hya"),
+    ("ひゅ", "hyu"),
+    ("ひょ", "hyo"),
+    ("みゃ", "mya"),
+    ("みゅ", "myu"),
+    ("みょ", "myo"),
+    ("りゃ", "rya"),
+    ("りゅ", "ryu"),
+    ("りょ", "ryo"),
+    ("ぎゃ", "gya"),
+    ("ぎゅ", "gyu"),
+    ("ぎょ", "gyo"),
+    ("じゃ", "ja"),
+    ("じゅ", "ju"),
+    ("じょ", "jo"),
+    ("びゃ", "bya"),
+    ("びゅ", "byu"),
+    ("びょ", "byo"),
+    ("ぴゃ", "pya"),
+    ("ぴゅ", "pyu"),
+    ("ぴょ", "pyo"),
+];
+
+const KANA: &[(char, &str)] = &[
+    ('あ', "a"),
+    ('い', "i"),
+    ('う', "u"),
+    ('え', "e"),
+    ('お', "o"),
+    ('か', "ka"),
+    ('き', "ki"),
+    ('く', "ku"),
+    ('け', "ke"),
+    ('こ', "ko"),
+    ('さ', "sa"),
+    ('し', "shi"),
+    ('す', "su"),
+    ('せ', "se"),
+    ('そ', "so"),
+    ('た', "ta"),
+    ('ち', "chi"),
+    ('つ', "tsu"),
+    ('て', "te"),
+    ('と', "to"),
+    ('な', "na"),
+    ('に', "ni"),
+    ('ぬ', "nu"),
+    ('ね', "ne"),
+    ('の', "no"),
+    ('は', "ha"),
+    ('ひ', "hi"),
+    ('ふ', "fu"),
+    ('へ', "he"),
+    ('ほ', "ho"),
+    ('ま', "ma"),
+    ('み', "mi"),
+    ('む', "mu"),
+    ('め', "me"),
+    ('も', "mo"),
+    ('や', "ya"),
+    ('ゆ', "yu"),
+    ('よ', "yo"),
+    ('ら', "ra"),
+    ('り', "ri"),
+    ('る', "ru"),
+    ('れ', "re"),
+    ('ろ', "ro"),
+    ('わ', "wa"),
+    ('ゐ', "i"),
+    ('ゑ', "e"),
+    ('を', "o"),
+    ('ん', "n"),
+    ('が', "ga"),
+    ('ぎ', "gi"),
+    ('ぐ', "gu"),
+    ('げ', "ge"),
+    ('ご', "go"),
+    ('ざ', "za"),
+    ('じ', "ji"),
+    ('ず', "zu"),
+    ('ぜ', "ze"),
+    ('ぞ', "zo"),
+    ('だ', "da"),
+    ('ぢ', "ji"),
+    ('づ', "zu"),
+    ('で', "de"),
+    ('ど', "do"),
+    ('ば', "ba"),
+    ('び', "bi"),
+    ('ぶ', "bu"),
+    ('べ', "be"),
+    ('ぼ', "bo"),
+    ('ぱ', "pa"),
+    ('ぴ', "pi"),
+    ('ぷ', "pu"),
+    ('ぺ', "pe"),
+    ('ぽ', "po"),
+    ('ぁ', "a"),
+    ('ぃ', "i"),
+    ('ぅ', "u"),
+    ('ぇ', "e"),
+    ('ぉ', "o"),
+    ('ゃ', "ya"),
+    ('ゅ', "yu"),
+    ('ょ', "yo"),
+    ('ゔ', "vu"),
+    ('。', "."),
+    ('、', ","),
+    ('！', "!"),
+    ('？', "?"),
+    ('　', " "),
+];
+
+/// Fold katakana onto the matching hiragana code point
+fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn kana_to_romaji(text: &str) -> String {
+    let original: Vec<char> = text.chars().collect();
+    let chars: Vec<char> = original.iter().copied().map(to_hiragana).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    // Small tsu doubles the consonant of the following syllable
+    let mut geminate = false;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == 'っ' {
+            geminate = true;
+            i += 1;
+            continue;
+        }
+
+        // The long vowel mark repeats the previous vowel
+        if c == 'ー' {
+            if let Some(last) = out.chars().last().filter(|l| "aeiou".contains(*l)) {
+                out.push(last);
+            }
+            i += 1;
+            continue;
+        }
+
+        let digraph = chars.get(i..i + 2).and_then(|pair| {
+            let pair: String = pair.iter().collect();
+            KANA_DIGRAPHS
+                .iter()
+                .find(|(kana, _)| *kana == pair)
+                .map(|(_, romaji)| (*romaji, 2))
+        });
+        let syllable = digraph.or_else(|| {
+            KANA.iter()
+                .find(|(kana, _)| *kana == c)
+                .map(|(_, romaji)| (*romaji, 1))
+        });
+
+        match syllable {
+            Some((romaji, len)) => {
+                if geminate {
+                    if let Some(first) = romaji.chars().next().filter(|f| !"aeiou".contains(*f)) {
+                        // Hepburn writes っち as "tch"
+                        out.push(if romaji.starts_with("ch") { 't' } else { first });
+                    }
+                }
+                out.push_str(romaji);
+                i += len;
+            }
+            None => {
+                // Kanji and other characters need a dictionary, so they pass through.
+                // The original is kept since folding can land outside KANA (ヶ to ゖ).
+                out.push(original[i]);
+                i += 1;
+            }
+        }
+
+        geminate = false;
+    }
+
+    out
+}
+
+/// Transliterate text into the requested script
+pub fn transliterate(text: &str, script: Script) -> String {
+    match script {
+        Script::Romaji => kana_to_romaji(text),
+    }
+}
+
+/// Post-process a transcript, keeping the native text alongside the transliteration
+pub fn apply_script(text: String, script: Option<Script>) -> ScriptedTranscript {
+    let transliterated = script.map(|s| transliterate(&text, s));
+    ScriptedTranscript {
+        text,
+        transliterated,
+    }
+}
+
+#[tauri::command]
+pub fn transliterate_transcript(
+    text: String,
+    script: Option<Script>,
+) -> Result<ScriptedTranscript, String> {
+    Ok(apply_script(text, script))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romanizes_syllables_and_digraphs() {
+        assert_eq!(kana_to_romaji("さくら"), "sakura");
+        assert_eq!(kana_to_romaji("ふじさん"), "fujisan");
+        assert_eq!(kana_to_romaji("とうきょう"), "toukyou");
+        assert_eq!(kana_to_romaji("しゃしん、じゃあね。"), "shashin,jaane.");
+    }
+
+    #[test]
+    fn small_tsu_doubles_the_next_consonant() {
+        assert_eq!(kana_to_romaji("きって"), "kitte");
+        assert_eq!(kana_to_romaji("ざっし"), "zasshi");
+        assert_eq!(kana_to_romaji("まっちゃ"), "matcha");
+        assert_eq!(kana_to_romaji("いっち"), "itchi");
+    }
+
+    #[test]
+    fn katakana_and_long_vowels() {
+        assert_eq!(kana_to_romaji("カタカナ"), "katakana");
+        assert_eq!(kana_to_romaji("ラーメン"), "raamen");
+        assert_eq!(kana_to_romaji("コーヒー"), "koohii");
+    }
+
+    #[test]
+    fn kanji_and_unmapped_kana_pass_through() {
+        assert_eq!(kana_to_romaji("一ヶ月"), "一ヶ月");
+        assert_eq!(kana_to_romaji("日本へようこそ"), "日本heyoukoso");
+        assert_eq!(kana_to_romaji("Flow"), "Flow");
+    }
+}
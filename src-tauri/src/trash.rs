@@ -0,0 +1,340 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::settings::{load_settings, update_settings};
+use crate::{
+    build_entry_info, entry_cache, get_journal_dir, notebook_dir, unique_filename, write_atomic,
+    EntryInfo,
+};
+
+pub const TRASH_DIR: &str = ".trash";
+// A TrashRecord for each trashed item, keyed by its name in the trash
+const ORIGINS_FILE: &str = ".origins.json";
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Where a trashed item came from and when it was deleted
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct TrashRecord {
+    /// Notebook it was deleted from; None for the top-level journal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notebook: Option<String>,
+    /// Seconds since the Unix epoch
+    #[serde(default)]
+    deleted_at: u64,
+}
+
+impl TrashRecord {
+    fn deleted_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.deleted_at)
+    }
+}
+
+pub fn get_trash_dir() -> PathBuf {
+    get_journal_dir().join(TRASH_DIR)
+}
+
+fn read_records() -> BTreeMap<String, TrashRecord> {
+    fs::read_to_string(get_trash_dir().join(ORIGINS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_records(records: &BTreeMap<String, TrashRecord>) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(records).map_err(|e| e.to_string())?;
+    write_atomic(&get_trash_dir().join(ORIGINS_FILE), &content)
+}
+
+/// Forget a trashed item's record, returning it if it had one
+fn take_record(trashed_name: &str) -> Option<TrashRecord> {
+    let mut records = read_records();
+    let record = records.remove(trashed_name)?;
+    if let Err(e) = write_records(&records) {
+        eprintln!("Failed to update trash records: {}", e);
+    }
+    Some(record)
+}
+
+/// Move an entry into the trash, recording the deletion time the retention sweep
+/// ages it by and, for an entry from a notebook, the notebook to restore it to.
+/// The move is undone if the record can't be written. Returns the name it has in
+/// the trash.
+pub fn move_to_trash(
+    path: &Path,
+    filename: &str,
+    notebook: Option<&str>,
+) -> Result<String, String> {
+    let trash_dir = get_trash_dir();
+    fs::create_dir_all(&trash_dir).map_err(|e| format!("Failed to create trash folder: {}", e))?;
+
+    let trashed_name = unique_filename(&trash_dir, filename.trim_end_matches(".md"));
+    let trashed_path = trash_dir.join(&trashed_name);
+    fs::rename(path, &trashed_path).map_err(|e| e.to_string())?;
+
+    let deleted_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut records = read_records();
+    records.insert(
+        trashed_name.clone(),
+        TrashRecord {
+            notebook: notebook
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(String::from),
+            deleted_at,
+        },
+    );
+
+    // Without a record the sweep would age the item by its old mtime and could
+    // purge it straight away, so put it back instead
+    if let Err(e) = write_records(&records) {
+        fs::rename(&trashed_path, path).ok();
+        return Err(format!("Failed to record deletion: {}", e));
+    }
+
+    entry_cache::invalidate(path);
+    Ok(trashed_name)
+}
+
+/// Entries currently in the trash
+#[tauri::command]
+pub fn list_trash() -> Result<Vec<EntryInfo>, String> {
+    let trash_dir = get_trash_dir();
+    if !trash_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(&trash_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("md") {
+            continue;
+        }
+
+        if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
+            if let Ok(content) = fs::read_to_string(&path) {
+                let size_bytes = entry
+                    .metadata()
+                    .map(|m| m.len())
+                    .unwrap_or(content.len() as u64);
+                entries.push(build_entry_info(filename, &content, size_bytes));
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+    Ok(entries)
+}
+
+/// Move an entry out of the trash back into the notebook it was deleted from (or
+/// the top-level journal). A name already taken there gets a numeric suffix.
+/// Returns the restored filename.
+#[tauri::command]
+pub fn restore_entry(filename: String) -> Result<String, String> {
+    let trashed_path = get_trash_dir().join(&filename);
+    if !trashed_path.is_file() {
+        return Err("Entry is not in the trash".to_string());
+    }
+
+    let notebook = read_records()
+        .get(&filename)
+        .and_then(|record| record.notebook.clone());
+    let journal_dir = notebook_dir(notebook.as_deref())?;
+    fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
+
+    let restored_name = unique_filename(&journal_dir, filename.trim_end_matches(".md"));
+    let restored_path = journal_dir.join(&restored_name);
+
+    fs::rename(&trashed_path, &restored_path).map_err(|e| e.to_string())?;
+    entry_cache::invalidate(&restored_path);
+    take_record(&filename);
+
+    Ok(restored_name)
+}
+
+/// Permanently remove an entry from the trash
+#[tauri::command]
+pub fn purge_entry(filename: String) -> Result<(), String> {
+    let trashed_path = get_trash_dir().join(&filename);
+    if !trashed_path.is_file() {
+        return Err("Entry is not in the trash".to_string());
+    }
+
+    fs::remove_file(&trashed_path).map_err(|e| e.to_string())?;
+    take_record(&filename);
+    Ok(())
+}
+
+/// Permanently remove trashed items older than the retention period.
+/// Items are aged by the deletion time in their record; only an item without one
+/// falls back to its modification time.
+/// Returns the number of items purged.
+pub fn purge_expired_trash(retention_days: u32) -> Result<usize, String> {
+    // A retention of 0 days keeps trashed items forever
+    if retention_days == 0 {
+        return Ok(0);
+    }
+
+    let trash_dir = get_trash_dir();
+    if !trash_dir.exists() {
+        return Ok(0);
+    }
+
+    let max_age = Duration::from_secs(retention_days as u64 * SECS_PER_DAY);
+    let now = SystemTime::now();
+    let mut records = read_records();
+    let mut purged = 0;
+
+    for entry in fs::read_dir(&trash_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == ORIGINS_FILE {
+            continue;
+        }
+
+        let deleted_at = match records.get(&name) {
+            Some(record) => record.deleted_at(),
+            None => match entry.metadata().and_then(|m| m.modified()) {
+                Ok(t) => t,
+                Err(_) => continue,
+            },
+        };
+
+        // Items stamped in the future (clock changes) are never considered expired
+        let age = match now.duration_since(deleted_at) {
+            Ok(age) => age,
+            Err(_) => continue,
+        };
+
+        if age < max_age {
+            continue;
+        }
+
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+
+        match result {
+            Ok(()) => {
+                purged += 1;
+                records.remove(&name);
+            }
+            Err(e) => eprintln!("Failed to purge {}: {}", path.display(), e),
+        }
+    }
+
+    if purged > 0 {
+        write_records(&records)?;
+    }
+    Ok(purged)
+}
+
+/// Sweep the trash using the configured retention period
+pub fn purge_trash_with_settings() -> Result<usize, String> {
+    purge_expired_trash(load_settings().trash_retention_days)
+}
+
+#[tauri::command]
+pub fn get_trash_retention_days() -> Result<u32, String> {
+    Ok(load_settings().trash_retention_days)
+}
+
+/// Persist a new retention period and immediately sweep with it.
+/// Returns the number of items purged.
+#[tauri::command]
+pub fn set_trash_retention_days(days: u32) -> Result<usize, String> {
+    update_settings(|s| s.trash_retention_days = days)?;
+    purge_expired_trash(days)
+}
+
+#[tauri::command]
+pub fn purge_expired_trash_now() -> Result<usize, String> {
+    purge_trash_with_settings()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{entry, set_mtime, TestJournal};
+
+    #[test]
+    fn delete_list_and_restore_round_trip() {
+        let journal = TestJournal::new();
+        let content = entry("Gone for now", "March 1, 2024", &[], "Body");
+        journal.write("a.md", &content);
+
+        crate::delete_entry("a.md".into(), None).unwrap();
+        assert!(!journal.root().join("a.md").exists());
+
+        let trashed = list_trash().unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].filename, "a.md");
+        assert_eq!(trashed[0].title, "Gone for now");
+
+        assert_eq!(restore_entry("a.md".into()).unwrap(), "a.md");
+        assert_eq!(journal.read("a.md"), content);
+        assert!(list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn restore_keeps_an_entry_created_in_the_meantime() {
+        let journal = TestJournal::new();
+        journal.write("a.md", "old");
+        crate::delete_entry("a.md".into(), None).unwrap();
+        journal.write("a.md", "new");
+
+        assert_eq!(restore_entry("a.md".into()).unwrap(), "a (2).md");
+        assert_eq!(journal.read("a.md"), "new");
+        assert_eq!(journal.read("a (2).md"), "old");
+    }
+
+    #[test]
+    fn sweep_ages_by_the_recorded_deletion_time() {
+        let journal = TestJournal::new();
+        let old = journal.write("old.md", "written long ago");
+        // An old mtime that a failed stamp would have left in place
+        set_mtime(
+            &old,
+            SystemTime::now() - Duration::from_secs(90 * SECS_PER_DAY),
+        );
+        crate::delete_entry("old.md".into(), None).unwrap();
+
+        assert_eq!(purge_expired_trash(30).unwrap(), 0);
+        assert_eq!(list_trash().unwrap().len(), 1);
+
+        // Backdate the record itself: now it has been in the trash past retention
+        let mut records = read_records();
+        records.get_mut("old.md").unwrap().deleted_at -= 31 * SECS_PER_DAY;
+        write_records(&records).unwrap();
+
+        assert_eq!(purge_expired_trash(30).unwrap(), 1);
+        assert!(list_trash().unwrap().is_empty());
+        assert!(read_records().is_empty());
+    }
+
+    #[test]
+    fn move_is_undone_when_the_record_cant_be_written() {
+        let journal = TestJournal::new();
+        let path = journal.write("a.md", "keep me");
+        // A folder where the records file goes makes writing it fail
+        fs::create_dir_all(get_trash_dir().join(ORIGINS_FILE)).unwrap();
+
+        assert!(crate::delete_entry("a.md".into(), None).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep me");
+        assert!(!get_trash_dir().join("a.md").exists());
+    }
+}
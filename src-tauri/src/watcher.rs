@@ -0,0 +1,173 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// Quiet period before a burst of events for one file is reported
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+#[derive(Serialize, Clone)]
+struct JournalChange {
+    kind: ChangeKind,
+    filename: String,
+    /// Notebook the entry is in; None for the journal root
+    notebook: Option<String>,
+}
+
+/// Holds the running watcher; dropping it stops watching and ends the debounce thread
+#[derive(Default)]
+pub struct JournalWatcher(Mutex<Option<RecommendedWatcher>>);
+
+impl JournalWatcher {
+    pub fn stop(&self) {
+        if let Ok(mut watcher) = self.0.lock() {
+            watcher.take();
+        }
+    }
+}
+
+/// Notebook (None for the journal root) and filename of the entry at path, or None
+/// if path isn't an entry, e.g. it's under .trash, .history, models/ or templates/
+fn entry_location(path: &Path, journal_dir: &Path) -> Option<(Option<String>, String)> {
+    if path.extension().and_then(|s| s.to_str()) != Some("md") {
+        return None;
+    }
+
+    let relative = path.strip_prefix(journal_dir).ok()?;
+    let mut parts = relative.iter().map(|part| part.to_str());
+
+    match (parts.next()??, parts.next(), parts.next()) {
+        (filename, None, _) => Some((None, filename.to_string())),
+        (notebook, Some(Some(filename)), None) => crate::validate_notebook_name(notebook)
+            .ok()
+            .map(|notebook| (Some(notebook.to_string()), filename.to_string())),
+        _ => None,
+    }
+}
+
+/// Collapse bursts of events per file and emit one `journal-changed` event for each
+fn debounce_events(rx: Receiver<(PathBuf, ChangeKind)>, app: AppHandle, journal_dir: PathBuf) {
+    // First kind seen for each file in the current burst
+    let mut pending: HashMap<PathBuf, ChangeKind> = HashMap::new();
+
+    loop {
+        let received = if pending.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(DEBOUNCE)
+        };
+
+        match received {
+            Ok((path, kind)) => {
+                pending.entry(path).or_insert(kind);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        for (path, first_kind) in pending.drain() {
+            // The file's state at the end of the burst decides what is reported
+            let kind = if !path.exists() {
+                ChangeKind::Removed
+            } else if first_kind == ChangeKind::Created {
+                ChangeKind::Created
+            } else {
+                ChangeKind::Modified
+            };
+
+            if let Some((notebook, filename)) = entry_location(&path, &journal_dir) {
+                let change = JournalChange {
+                    kind,
+                    filename,
+                    notebook,
+                };
+                if let Err(e) = app.emit("journal-changed", change) {
+                    eprintln!("Failed to emit journal change: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Watch the journal dir and its notebooks, emitting `journal-changed` events to the
+/// frontend. Calling it again replaces the running watcher, e.g. after the journal moves.
+pub fn start_watching(app: &AppHandle, state: &JournalWatcher) -> Result<(), String> {
+    let journal_dir = crate::get_journal_dir();
+    fs::create_dir_all(&journal_dir).map_err(|e| e.to_string())?;
+
+    let (tx, rx) = mpsc::channel();
+    let watched_dir = journal_dir.clone();
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let event = match result {
+            Ok(event) => event,
+            // e.g. the journal dir was removed out from under us
+            Err(e) => {
+                eprintln!("Journal watcher error: {}", e);
+                return;
+            }
+        };
+
+        let kind = match event.kind {
+            EventKind::Create(_) => ChangeKind::Created,
+            EventKind::Modify(_) => ChangeKind::Modified,
+            EventKind::Remove(_) => ChangeKind::Removed,
+            _ => return,
+        };
+
+        for path in event.paths {
+            if entry_location(&path, &watched_dir).is_some() {
+                tx.send((path, kind)).ok();
+            }
+        }
+    })
+    .map_err(|e| e.to_string())?;
+
+    watcher
+        .watch(&journal_dir, RecursiveMode::Recursive)
+        .map_err(|e| e.to_string())?;
+
+    let app = app.clone();
+    std::thread::spawn(move || debounce_events(rx, app, journal_dir));
+
+    *state.0.lock().map_err(|e| format!("Lock error: {}", e))? = Some(watcher);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_entries_in_the_root_and_notebooks_only() {
+        let root = Path::new("/journal");
+        let located = |rel: &str| entry_location(&root.join(rel), root);
+
+        assert_eq!(located("day.md"), Some((None, "day.md".to_string())));
+        assert_eq!(
+            located("Work/day.md"),
+            Some((Some("Work".to_string()), "day.md".to_string()))
+        );
+
+        assert_eq!(located("day.txt"), None);
+        assert_eq!(located(".trash/day.md"), None);
+        assert_eq!(located(".history/day.md"), None);
+        assert_eq!(located("Work/.history/day.md"), None);
+        assert_eq!(located("templates/Daily.md"), None);
+        assert_eq!(located("Work/sub/day.md"), None);
+    }
+}
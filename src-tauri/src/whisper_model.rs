@@ -1,95 +1,1102 @@
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::fmt;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
-use futures_util::StreamExt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Window};
 
-const MODEL_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin";
-const MODEL_FILENAME: &str = "ggml-base.en.bin";
+use crate::settings::{load_settings, update_settings, APP_IDENTIFIER};
 
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+// Lists the repo's files along with the SHA-256 of each LFS object
+const MODEL_TREE_URL: &str = "https://huggingface.co/api/models/ggerganov/whisper.cpp/tree/main";
+
+// SHA-256 of each built-in model: the `lfs.oid` of its file at MODEL_TREE_URL.
+// Every built-in model belongs here (a test checks it) so verification works
+// offline. Until one is added its digest is fetched from MODEL_TREE_URL, and a
+// model whose digest can't be found is rejected rather than trusted.
+const MODEL_SHA256: &[(WhisperModel, &str)] = &[];
+
+// Set by cancel_model_download; the running download checks it between chunks
+static DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
+// Whether a cancelled download should delete its partial file instead of keeping it to resume
+static DISCARD_PARTIAL: AtomicBool = AtomicBool::new(false);
+
+/// Models live in the app data dir by default, apart from the journal, so moving
+/// or syncing the journal doesn't drag hundreds of MB of model data along
+fn default_models_dir() -> PathBuf {
+    let data_dir = dirs::data_dir().expect("Could not find data directory");
+    data_dir.join(APP_IDENTIFIER).join("models")
+}
+
+/// Where models were kept before they had their own setting
+fn legacy_models_dir() -> PathBuf {
+    crate::get_journal_dir().join("models")
+}
+
+/// The configured models folder, or the default when none is set
 pub fn get_models_dir() -> PathBuf {
-    let home = dirs::home_dir().expect("Could not find home directory");
-    home.join("Documents")
-        .join("Project Data Files")
-        .join("Journal")
-        .join("models")
+    load_settings()
+        .models_dir
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(default_models_dir)
 }
 
-pub fn get_model_path() -> PathBuf {
-    get_models_dir().join(MODEL_FILENAME)
+/// Create dir if needed and check files can be written to it
+fn ensure_writable(dir: &Path) -> Result<(), String> {
+    if dir.exists() && !dir.is_dir() {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create models directory: {}", e))?;
+
+    let probe = dir.join(".write-test");
+    fs::write(&probe, b"").map_err(|e| format!("Models directory is not writable: {}", e))?;
+    fs::remove_file(&probe).ok();
+    Ok(())
 }
 
-pub fn is_model_downloaded() -> bool {
-    let path = get_model_path();
-    if !path.exists() {
-        return false;
+/// Move a file, falling back to copy and delete when rename can't cross filesystems
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(from, to).map_err(|e| format!("Failed to copy {}: {}", from.display(), e))?;
+    fs::remove_file(from).map_err(|e| format!("Failed to remove {}: {}", from.display(), e))
+}
+
+/// Move models left in the journal's old models folder into the models dir,
+/// returning how many files were moved. Files already present at the destination
+/// are left where they are.
+pub fn migrate_legacy_models() -> Result<usize, String> {
+    let legacy_dir = legacy_models_dir();
+    let models_dir = get_models_dir();
+    if !legacy_dir.is_dir() || legacy_dir == models_dir {
+        return Ok(0);
+    }
+
+    let entries = fs::read_dir(&legacy_dir)
+        .map_err(|e| format!("Failed to read {}: {}", legacy_dir.display(), e))?;
+    let files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("ggml-"))
+        })
+        .collect();
+    if files.is_empty() {
+        return Ok(0);
+    }
+
+    ensure_writable(&models_dir)?;
+    let mut moved = 0;
+    for path in files {
+        let destination = models_dir.join(path.file_name().unwrap_or_default());
+        if destination.exists() {
+            continue;
+        }
+        move_file(&path, &destination)?;
+        moved += 1;
+    }
+
+    // Only succeeds once the folder is empty, so anything else in it is kept
+    fs::remove_dir(&legacy_dir).ok();
+    Ok(moved)
+}
+
+/// The ggml Whisper models that can be downloaded. ".en" models are English-only
+/// but a little more accurate on English; the others are multilingual. Custom is a
+/// user-supplied model, downloaded from `custom_model_url` or copied in from a file.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum WhisperModel {
+    #[serde(rename = "tiny")]
+    Tiny,
+    #[serde(rename = "tiny.en")]
+    TinyEn,
+    #[serde(rename = "base")]
+    Base,
+    #[default]
+    #[serde(rename = "base.en")]
+    BaseEn,
+    #[serde(rename = "small")]
+    Small,
+    #[serde(rename = "small.en")]
+    SmallEn,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "medium.en")]
+    MediumEn,
+    #[serde(rename = "large-v3")]
+    LargeV3,
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+impl WhisperModel {
+    pub const ALL: [WhisperModel; 10] = [
+        WhisperModel::Tiny,
+        WhisperModel::TinyEn,
+        WhisperModel::Base,
+        WhisperModel::BaseEn,
+        WhisperModel::Small,
+        WhisperModel::SmallEn,
+        WhisperModel::Medium,
+        WhisperModel::MediumEn,
+        WhisperModel::LargeV3,
+        WhisperModel::Custom,
+    ];
+
+    /// Name used in settings and in the ggml filename, e.g. "base.en"
+    pub fn name(self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "tiny",
+            WhisperModel::TinyEn => "tiny.en",
+            WhisperModel::Base => "base",
+            WhisperModel::BaseEn => "base.en",
+            WhisperModel::Small => "small",
+            WhisperModel::SmallEn => "small.en",
+            WhisperModel::Medium => "medium",
+            WhisperModel::MediumEn => "medium.en",
+            WhisperModel::LargeV3 => "large-v3",
+            WhisperModel::Custom => "custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|model| model.name() == name)
+    }
+
+    /// Name shown in the model picker, e.g. "Base (English)"
+    pub fn display_name(self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "Tiny",
+            WhisperModel::TinyEn => "Tiny (English)",
+            WhisperModel::Base => "Base",
+            WhisperModel::BaseEn => "Base (English)",
+            WhisperModel::Small => "Small",
+            WhisperModel::SmallEn => "Small (English)",
+            WhisperModel::Medium => "Medium",
+            WhisperModel::MediumEn => "Medium (English)",
+            WhisperModel::LargeV3 => "Large v3",
+            WhisperModel::Custom => "Custom",
+        }
+    }
+
+    pub fn is_english_only(self) -> bool {
+        self.name().ends_with(".en")
+    }
+
+    pub fn filename(self) -> String {
+        format!("ggml-{}.bin", self.name())
+    }
+
+    /// Where to download the model from: the bundled Hugging Face URL, or for Custom
+    /// the `custom_model_url` setting
+    pub fn url(self) -> Result<String, String> {
+        match self {
+            WhisperModel::Custom => load_settings()
+                .custom_model_url
+                .filter(|url| !url.trim().is_empty())
+                .ok_or_else(|| "No custom model URL is set".to_string()),
+            _ => Ok(format!("{}/{}", MODEL_BASE_URL, self.filename())),
+        }
+    }
+
+    /// Approximate download size, for progress when the server doesn't send a length
+    /// and for showing in the model picker
+    fn approx_size_bytes(self) -> u64 {
+        match self {
+            WhisperModel::Tiny | WhisperModel::TinyEn => 75_000_000,
+            WhisperModel::Base | WhisperModel::BaseEn | WhisperModel::Custom => 142_000_000,
+            WhisperModel::Small | WhisperModel::SmallEn => 466_000_000,
+            WhisperModel::Medium | WhisperModel::MediumEn => 1_500_000_000,
+            WhisperModel::LargeV3 => 3_100_000_000,
+        }
+    }
+}
+
+impl fmt::Display for WhisperModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The model selected in settings
+pub fn get_active_model() -> WhisperModel {
+    load_settings().whisper_model
+}
+
+pub fn get_model_path(model: WhisperModel) -> PathBuf {
+    get_models_dir().join(model.filename())
+}
+
+/// Where a model is downloaded to until it has been verified
+fn partial_path(model: WhisperModel) -> PathBuf {
+    get_models_dir().join(format!("{}.part", model.filename()))
+}
+
+// First four bytes of a ggml model (the magic 0x67676d6c, little-endian) and of the
+// newer GGUF format
+const GGML_MAGIC: [u8; 4] = *b"lmgg";
+const GGUF_MAGIC: [u8; 4] = *b"GGUF";
+
+/// Whether bytes start with a ggml/GGUF model header
+fn has_ggml_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GGML_MAGIC) || bytes.starts_with(&GGUF_MAGIC)
+}
+
+/// Whether the file at path starts with a ggml/GGUF model header
+fn is_ggml_file(path: &Path) -> bool {
+    let mut header = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| has_ggml_magic(&header))
+}
+
+/// Whether a model file is big enough to plausibly be complete. Only the built-in
+/// models have a known size; a custom model may be a small quantized one, so any
+/// file with a ggml header passes.
+fn has_model_size(model: WhisperModel, path: &Path) -> bool {
+    if model == WhisperModel::Custom {
+        return is_ggml_file(path);
+    }
+
+    // Check file size is reasonable (the smallest model, tiny, is ~75MB)
+    fs::metadata(path).is_ok_and(|metadata| metadata.len() > 50_000_000) // At least 50MB
+}
+
+pub fn is_model_downloaded(model: WhisperModel) -> bool {
+    has_model_size(model, &get_model_path(model))
+}
+
+/// Payload of the `whisper-download-progress` event
+#[derive(Serialize, Clone)]
+struct DownloadProgress {
+    percent: u8,
+    downloaded: u64,
+    total: u64,
+    bytes_per_sec: u64,
+    eta_secs: u64,
+}
+
+impl DownloadProgress {
+    fn complete(total: u64) -> Self {
+        Self {
+            percent: 100,
+            downloaded: total,
+            total,
+            bytes_per_sec: 0,
+            eta_secs: 0,
+        }
+    }
+}
+
+// Progress intervals averaged for the speed, so the ETA doesn't jump around
+const SPEED_WINDOW: usize = 5;
+
+/// Download speed as a moving average over the last few progress intervals
+#[derive(Default)]
+struct SpeedTracker {
+    samples: VecDeque<f64>,
+}
+
+impl SpeedTracker {
+    /// Add bytes received over elapsed and return the averaged bytes per second
+    fn record(&mut self, bytes: u64, elapsed: Duration) -> u64 {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.samples.push_back(bytes as f64 / secs);
+            if self.samples.len() > SPEED_WINDOW {
+                self.samples.pop_front();
+            }
+        }
+
+        if self.samples.is_empty() {
+            return 0;
+        }
+        (self.samples.iter().sum::<f64>() / self.samples.len() as f64) as u64
     }
-    // Check file size is reasonable (base.en is ~142MB)
-    if let Ok(metadata) = fs::metadata(&path) {
-        return metadata.len() > 100_000_000; // At least 100MB
+}
+
+/// Seconds left to fetch remaining bytes at bytes_per_sec; 0 while the speed is unknown
+fn eta_secs(remaining: u64, bytes_per_sec: u64) -> u64 {
+    if bytes_per_sec == 0 {
+        return 0;
+    }
+    remaining.div_ceil(bytes_per_sec)
+}
+
+/// Percentage of total_size downloaded so far, capped at 100
+fn download_percent(downloaded: u64, total_size: u64) -> u8 {
+    if total_size == 0 {
+        return 0;
+    }
+    ((downloaded as f64 / total_size as f64) * 100.0).min(100.0) as u8
+}
+
+/// Bytes already fetched by an interrupted download of model
+fn partial_len(model: WhisperModel) -> u64 {
+    fs::metadata(partial_path(model)).map_or(0, |m| m.len())
+}
+
+/// Offset a download continues from and the model's full size, given the bytes
+/// already on disk and the server's response. A server that ignores the range sends
+/// the whole file with 200, so the download starts over. Without a length the size
+/// falls back to approx_size.
+fn resume_point(
+    resume_from: u64,
+    partial_content: bool,
+    content_length: Option<u64>,
+    approx_size: u64,
+) -> (u64, u64) {
+    let offset = if partial_content { resume_from } else { 0 };
+    let total = content_length.map_or(approx_size, |remaining| remaining + offset);
+    (offset, total)
+}
+
+/// Write the chunks of stream to out and call on_progress each time another percent
+/// of total_size has arrived, counting from offset. Stops between chunks with
+/// "cancelled" once cancel_model_download is called. Returns the bytes downloaded,
+/// offset included.
+async fn write_chunks<S, B, E, W, P>(
+    mut stream: S,
+    out: &mut W,
+    offset: u64,
+    total_size: u64,
+    mut on_progress: P,
+) -> Result<u64, String>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: fmt::Display,
+    W: Write,
+    P: FnMut(DownloadProgress),
+{
+    let mut downloaded = offset;
+    let mut speed = SpeedTracker::default();
+    let mut last_percent = download_percent(downloaded, total_size);
+    let mut last_emit = Instant::now();
+    let mut bytes_at_last_emit = downloaded;
+
+    while let Some(chunk_result) = stream.next().await {
+        if DOWNLOAD_CANCELLED.swap(false, Ordering::SeqCst) {
+            return Err("cancelled".to_string());
+        }
+
+        let chunk = chunk_result.map_err(|e| format!("Download error: {}", e))?;
+        let chunk = chunk.as_ref();
+
+        out.write_all(chunk)
+            .map_err(|e| format!("Failed to write chunk: {}", e))?;
+
+        downloaded += chunk.len() as u64;
+        let percent = download_percent(downloaded, total_size);
+
+        // Emit progress every ~1%
+        if percent > last_percent {
+            let bytes_per_sec = speed.record(downloaded - bytes_at_last_emit, last_emit.elapsed());
+            on_progress(DownloadProgress {
+                percent,
+                downloaded,
+                total: total_size,
+                bytes_per_sec,
+                eta_secs: eta_secs(total_size.saturating_sub(downloaded), bytes_per_sec),
+            });
+
+            last_percent = percent;
+            last_emit = Instant::now();
+            bytes_at_last_emit = downloaded;
+        }
     }
-    false
+
+    out.flush()
+        .map_err(|e| format!("Failed to flush file: {}", e))?;
+    Ok(downloaded)
 }
 
 #[tauri::command]
 pub fn check_whisper_model() -> Result<bool, String> {
-    Ok(is_model_downloaded())
+    Ok(is_model_downloaded(get_active_model()))
 }
 
 #[tauri::command]
-pub async fn download_whisper_model(window: Window) -> Result<(), String> {
+pub fn get_current_model() -> Result<WhisperModel, String> {
+    Ok(get_active_model())
+}
+
+#[derive(Serialize)]
+pub struct ModelStatus {
+    model: WhisperModel,
+    display_name: &'static str,
+    approx_size_bytes: u64,
+    downloaded: bool,
+    multilingual: bool,
+}
+
+/// SHA-256 of a file as lowercase hex, read in chunks so large models aren't loaded whole
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where the expected digest of a model is kept once fetched
+fn checksum_path(model: WhisperModel) -> PathBuf {
+    get_models_dir().join(format!("{}.sha256", model.filename()))
+}
+
+/// Digest of a built-in model from MODEL_SHA256
+fn embedded_checksum(model: WhisperModel) -> Option<&'static str> {
+    MODEL_SHA256
+        .iter()
+        .find(|(known, _)| *known == model)
+        .map(|(_, digest)| *digest)
+}
+
+/// Expected SHA-256 of a model: the embedded digest, else a cached copy, else
+/// Hugging Face's file listing. None (with a warning) for a custom model, which
+/// has nothing to check against. A built-in model whose digest can't be found is
+/// an error, so it is never passed unchecked.
+async fn expected_checksum(model: WhisperModel) -> Result<Option<String>, String> {
+    if model == WhisperModel::Custom {
+        eprintln!("No checksum for the custom model; only its header will be checked");
+        return Ok(None);
+    }
+
+    if let Some(digest) = embedded_checksum(model) {
+        return Ok(Some(digest.to_string()));
+    }
+
+    if let Ok(cached) = fs::read_to_string(checksum_path(model)) {
+        let cached = cached.trim();
+        if !cached.is_empty() {
+            return Ok(Some(cached.to_string()));
+        }
+    }
+
+    let checksum = fetch_checksum(model)
+        .await
+        .map_err(|e| format!("Can't verify the {} model: {}", model, e))?;
+    fs::write(checksum_path(model), &checksum).ok();
+    Ok(Some(checksum))
+}
+
+/// Look up a model's SHA-256 in Hugging Face's file listing
+async fn fetch_checksum(model: WhisperModel) -> Result<String, String> {
+    let listing = reqwest::get(MODEL_TREE_URL)
+        .await
+        .map_err(|e| format!("Failed to fetch model checksums: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to fetch model checksums: {}", e))?;
+    let files: Vec<serde_json::Value> = serde_json::from_str(&listing)
+        .map_err(|e| format!("Invalid model checksum listing: {}", e))?;
+
+    let filename = model.filename();
+    files
+        .iter()
+        .find(|file| file["path"] == filename.as_str())
+        .and_then(|file| file["lfs"]["oid"].as_str())
+        .map(|oid| oid.to_lowercase())
+        .ok_or_else(|| format!("No checksum published for {}", filename))
+}
+
+/// Hash a model file off the async runtime and compare it to the expected digest
+async fn checksum_matches(path: PathBuf, expected: &str) -> Result<bool, String> {
+    let actual = tauri::async_runtime::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| format!("Checksum failed: {}", e))??;
+    Ok(actual == expected)
+}
+
+/// Hash the active model and compare it against its published SHA-256. False when
+/// the model is missing or doesn't match; an error when its digest can't be found.
+/// A custom model only gets its header checked.
+#[tauri::command]
+pub async fn verify_model() -> Result<bool, String> {
+    let model = get_active_model();
+
+    // The size check is a cheap first pass; hashing a large model takes a while
+    if !is_model_downloaded(model) {
+        return Ok(false);
+    }
+
+    let path = get_model_path(model);
+    if !is_ggml_file(&path) {
+        return Ok(false);
+    }
+
+    match expected_checksum(model).await? {
+        Some(expected) => checksum_matches(path, &expected).await,
+        None => Ok(true),
+    }
+}
+
+/// Copy a ggml model file into the models dir and make it the active (custom) model
+#[tauri::command]
+pub fn set_model_from_file(path: String) -> Result<(), String> {
+    let source = Path::new(&path);
+    if !source.is_file() {
+        return Err("Model file not found".to_string());
+    }
+    if !is_ggml_file(source) {
+        return Err("Not a ggml Whisper model".to_string());
+    }
+
     let models_dir = get_models_dir();
-    fs::create_dir_all(&models_dir).map_err(|e| format!("Failed to create models directory: {}", e))?;
+    fs::create_dir_all(&models_dir)
+        .map_err(|e| format!("Failed to create models directory: {}", e))?;
+
+    // Copied under a temporary name so a failed copy never replaces a working model
+    let model = WhisperModel::Custom;
+    let part_path = partial_path(model);
+    fs::copy(source, &part_path).map_err(|e| format!("Failed to copy model: {}", e))?;
+
+    if crate::transcription::loaded_model() == Some(model) {
+        crate::transcription::unload_model();
+    }
+    fs::rename(&part_path, get_model_path(model))
+        .map_err(|e| format!("Failed to save model: {}", e))?;
+
+    update_settings(|s| s.whisper_model = model)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_models_dir_setting() -> Result<String, String> {
+    Ok(get_models_dir().to_string_lossy().into_owned())
+}
+
+/// Keep models in a different folder, creating it if needed. An empty path goes
+/// back to the default location. Models already downloaded are not moved.
+#[tauri::command]
+pub fn set_models_dir(path: String) -> Result<(), String> {
+    let path = path.trim();
+
+    if path.is_empty() {
+        update_settings(|s| s.models_dir = None)?;
+        return Ok(());
+    }
+
+    ensure_writable(Path::new(path))?;
+    update_settings(|s| s.models_dir = Some(path.to_string()))?;
+    Ok(())
+}
 
-    let model_path = get_model_path();
+/// Set (or with None, clear) the URL the custom model is downloaded from
+#[tauri::command]
+pub fn set_custom_model_url(url: Option<String>) -> Result<(), String> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if let Some(url) = &url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("Invalid model URL: {}", url));
+        }
+    }
+
+    update_settings(|s| s.custom_model_url = url)?;
+    Ok(())
+}
+
+/// Stop the model download in progress, which then returns a "cancelled" error. The
+/// partial file is kept so the next download resumes, unless discard_partial is set.
+#[tauri::command]
+pub fn cancel_model_download(discard_partial: Option<bool>) {
+    DISCARD_PARTIAL.store(discard_partial.unwrap_or(false), Ordering::SeqCst);
+    DOWNLOAD_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Delete a downloaded model, unloading it first if it is the one in use
+#[tauri::command]
+pub fn delete_model(model: WhisperModel) -> Result<(), String> {
+    let path = get_model_path(model);
+    if !path.exists() {
+        return Err(format!("Model {} is not downloaded", model));
+    }
+
+    // Release the context so the file isn't held open (or mapped) while removing it
+    if crate::transcription::loaded_model() == Some(model) {
+        crate::transcription::unload_model();
+    }
+
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete model: {}", e))?;
+    fs::remove_file(checksum_path(model)).ok();
+    fs::remove_file(partial_path(model)).ok();
+    Ok(())
+}
+
+/// Total size in bytes of everything in the models dir, partial downloads included
+#[tauri::command]
+pub fn models_disk_usage() -> Result<u64, String> {
+    let entries = match fs::read_dir(get_models_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read models directory: {}", e)),
+    };
+
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum())
+}
+
+/// Every available model, smallest first, and whether it has been downloaded
+#[tauri::command]
+pub fn list_models() -> Result<Vec<ModelStatus>, String> {
+    let mut models: Vec<ModelStatus> = WhisperModel::ALL
+        .into_iter()
+        .map(|model| ModelStatus {
+            model,
+            display_name: model.display_name(),
+            approx_size_bytes: model.approx_size_bytes(),
+            downloaded: is_model_downloaded(model),
+            multilingual: !model.is_english_only(),
+        })
+        .collect();
+    models.sort_by_key(|status| status.approx_size_bytes);
+
+    Ok(models)
+}
+
+/// Make another model active. The loaded context is released right away when idle;
+/// if a transcription is running, the swap happens on the next transcription instead.
+#[tauri::command]
+pub fn switch_model(model: WhisperModel) -> Result<(), String> {
+    update_settings(|s| s.whisper_model = model)?;
+    crate::transcription::unload_model_if_idle();
+
+    Ok(())
+}
+
+/// Download a model (the active one when None), emitting `whisper-download-progress`
+#[tauri::command]
+pub async fn download_whisper_model(
+    window: Window,
+    model: Option<WhisperModel>,
+) -> Result<(), String> {
+    let models_dir = get_models_dir();
+    ensure_writable(&models_dir)?;
+
+    let model = model.unwrap_or_else(get_active_model);
+    let model_path = get_model_path(model);
 
     // If already downloaded, skip
-    if is_model_downloaded() {
-        let _ = window.emit("whisper-download-progress", 100u8);
+    if is_model_downloaded(model) {
+        let size = fs::metadata(&model_path).map_or(0, |m| m.len());
+        let _ = window.emit(
+            "whisper-download-progress",
+            DownloadProgress::complete(size),
+        );
         return Ok(());
     }
 
+    // Fetched up front so a finished download can always be checked
+    let expected = expected_checksum(model).await?;
+
+    // A cancel aimed at an earlier download shouldn't stop this one
+    DOWNLOAD_CANCELLED.store(false, Ordering::SeqCst);
+
+    // Pick up where an interrupted download left off
+    let part_path = partial_path(model);
+    let resume_from = partial_len(model);
+
     // Download the model
     let client = reqwest::Client::new();
-    let response = client
-        .get(MODEL_URL)
+    let mut request = client.get(model.url()?);
+    if resume_from > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+    }
+    let response = request
         .send()
         .await
         .map_err(|e| format!("Failed to start download: {}", e))?;
 
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file is no prefix of the model (e.g. it was replaced upstream)
+        fs::remove_file(&part_path).ok();
+        return Err(
+            "Partial download could not be resumed and was discarded. Please try again."
+                .to_string(),
+        );
+    }
+
     if !response.status().is_success() {
-        return Err(format!("Download failed with status: {}", response.status()));
+        return Err(format!(
+            "Download failed with status: {}",
+            response.status()
+        ));
     }
 
-    let total_size = response.content_length().unwrap_or(142_000_000);
-    let mut downloaded: u64 = 0;
+    let (offset, total_size) = resume_point(
+        resume_from,
+        response.status() == reqwest::StatusCode::PARTIAL_CONTENT,
+        response.content_length(),
+        model.approx_size_bytes(),
+    );
+    let resumed = offset > 0;
 
-    let mut file = fs::File::create(&model_path)
-        .map_err(|e| format!("Failed to create model file: {}", e))?;
+    let mut file = if resumed {
+        fs::OpenOptions::new().append(true).open(&part_path)
+    } else {
+        fs::File::create(&part_path)
+    }
+    .map_err(|e| format!("Failed to create model file: {}", e))?;
 
-    let mut stream = response.bytes_stream();
+    let result = write_chunks(
+        response.bytes_stream(),
+        &mut file,
+        offset,
+        total_size,
+        |progress| {
+            let _ = window.emit("whisper-download-progress", progress);
+        },
+    )
+    .await;
+    drop(file);
 
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| format!("Download error: {}", e))?;
+    let downloaded = match result {
+        Err(e) if e == "cancelled" => {
+            if DISCARD_PARTIAL.load(Ordering::SeqCst) {
+                fs::remove_file(&part_path).ok();
+            }
+            return Err(e);
+        }
+        result => result?,
+    };
 
-        file.write_all(&chunk)
-            .map_err(|e| format!("Failed to write chunk: {}", e))?;
+    // Verify download
+    if !has_model_size(model, &part_path) {
+        fs::remove_file(&part_path).ok();
+        return Err("Download verification failed - file may be incomplete".to_string());
+    }
 
-        downloaded += chunk.len() as u64;
-        let progress = ((downloaded as f64 / total_size as f64) * 100.0) as u8;
+    if !is_ggml_file(&part_path) {
+        fs::remove_file(&part_path).ok();
+        return Err("Download verification failed - not a ggml Whisper model".to_string());
+    }
+    if let Some(expected) = expected {
+        if !checksum_matches(part_path.clone(), &expected).await? {
+            fs::remove_file(&part_path).ok();
+            return Err(
+                "Download verification failed - checksum mismatch, the file may be corrupted"
+                    .to_string(),
+            );
+        }
+    }
 
-        // Emit progress every ~1%
-        let _ = window.emit("whisper-download-progress", progress);
+    // Only a verified model takes the real name
+    fs::rename(&part_path, &model_path).map_err(|e| format!("Failed to save model: {}", e))?;
+
+    let _ = window.emit(
+        "whisper-download-progress",
+        DownloadProgress::complete(downloaded),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::test_support::TestJournal;
+
+    #[test]
+    fn every_model_maps_to_its_file_and_url() {
+        let _journal = TestJournal::new();
+        let expected = [
+            (WhisperModel::Tiny, "ggml-tiny.bin"),
+            (WhisperModel::TinyEn, "ggml-tiny.en.bin"),
+            (WhisperModel::Base, "ggml-base.bin"),
+            (WhisperModel::BaseEn, "ggml-base.en.bin"),
+            (WhisperModel::Small, "ggml-small.bin"),
+            (WhisperModel::SmallEn, "ggml-small.en.bin"),
+            (WhisperModel::Medium, "ggml-medium.bin"),
+            (WhisperModel::MediumEn, "ggml-medium.en.bin"),
+            (WhisperModel::LargeV3, "ggml-large-v3.bin"),
+        ];
+
+        for (model, filename) in expected {
+            assert_eq!(model.filename(), filename);
+            assert_eq!(
+                model.url().unwrap(),
+                format!(
+                    "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{}",
+                    filename
+                )
+            );
+            assert_eq!(WhisperModel::from_name(model.name()), Some(model));
+        }
+
+        // Without a URL set there is nowhere to fetch the custom model from
+        assert_eq!(WhisperModel::Custom.filename(), "ggml-custom.bin");
+        assert!(WhisperModel::Custom.url().is_err());
     }
 
-    file.flush().map_err(|e| format!("Failed to flush file: {}", e))?;
+    #[test]
+    fn custom_model_url_comes_from_settings() {
+        let _journal = TestJournal::with_settings(Settings {
+            custom_model_url: Some("https://example.com/fine-tuned.bin".into()),
+            ..Default::default()
+        });
 
-    // Verify download
-    if !is_model_downloaded() {
-        fs::remove_file(&model_path).ok();
-        return Err("Download verification failed - file may be incomplete".to_string());
+        assert_eq!(
+            WhisperModel::Custom.url().unwrap(),
+            "https://example.com/fine-tuned.bin"
+        );
     }
 
-    let _ = window.emit("whisper-download-progress", 100u8);
-    Ok(())
+    #[test]
+    fn hashes_a_file_with_a_known_digest() {
+        let journal = TestJournal::new();
+        let abc = journal.outside("abc.txt");
+        fs::write(&abc, "abc").unwrap();
+        let empty = journal.outside("empty.txt");
+        fs::write(&empty, "").unwrap();
+
+        assert_eq!(
+            sha256_file(&abc).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256_file(&empty).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(sha256_file(&journal.outside("missing.bin")).is_err());
+    }
+
+    #[test]
+    fn resumes_from_the_partial_file() {
+        let _journal = TestJournal::new();
+        let model = WhisperModel::Tiny;
+        assert_eq!(partial_len(model), 0);
+
+        fs::create_dir_all(get_models_dir()).unwrap();
+        fs::write(partial_path(model), vec![0u8; 40_000]).unwrap();
+        let resume_from = partial_len(model);
+        assert_eq!(resume_from, 40_000);
+
+        // 206 with the remaining 60,000 bytes: progress starts at 40%
+        let (offset, total) = resume_point(resume_from, true, Some(60_000), 75_000_000);
+        assert_eq!((offset, total), (40_000, 100_000));
+        assert_eq!(download_percent(offset, total), 40);
+
+        // 200 with the whole file: the range was ignored, so start again from zero
+        assert_eq!(
+            resume_point(resume_from, false, Some(100_000), 75_000_000),
+            (0, 100_000)
+        );
+
+        // No length sent: fall back to the approximate size
+        assert_eq!(
+            resume_point(resume_from, true, None, 75_000_000),
+            (40_000, 75_000_000)
+        );
+    }
+
+    /// A download of four 25-byte chunks
+    fn chunks() -> impl Stream<Item = Result<Vec<u8>, String>> + Unpin {
+        futures_util::stream::iter((0..4).map(|_| Ok(vec![1u8; 25])))
+    }
+
+    #[test]
+    fn cancelling_stops_the_download_at_the_next_chunk() {
+        let mut out = Vec::new();
+        let mut updates = 0;
+
+        // Cancelled as soon as the first chunk has been reported
+        let result =
+            tauri::async_runtime::block_on(write_chunks(chunks(), &mut out, 0, 100, |_| {
+                updates += 1;
+                cancel_model_download(None);
+            }));
+
+        assert_eq!(result, Err("cancelled".to_string()));
+        assert_eq!(out.len(), 25);
+        assert_eq!(updates, 1);
+        // The flag is used up, so the next download isn't cancelled too
+        assert!(!DOWNLOAD_CANCELLED.load(Ordering::SeqCst));
+    }
+
+    /// Put a file of len bytes (sparse, so it costs no disk) in the models dir
+    fn fake_model_file(name: &str, len: u64) {
+        let models_dir = get_models_dir();
+        fs::create_dir_all(&models_dir).unwrap();
+        fs::File::create(models_dir.join(name))
+            .and_then(|file| file.set_len(len))
+            .unwrap();
+    }
+
+    #[test]
+    fn deleted_model_is_no_longer_downloaded() {
+        let _journal = TestJournal::new();
+        let model = WhisperModel::Small;
+        fake_model_file(&model.filename(), 60_000_000);
+        fake_model_file(&format!("{}.sha256", model.filename()), 64);
+        assert!(is_model_downloaded(model));
+        assert_eq!(models_disk_usage().unwrap(), 60_000_064);
+
+        delete_model(model).unwrap();
+
+        assert!(!is_model_downloaded(model));
+        assert_eq!(models_disk_usage().unwrap(), 0);
+        assert!(delete_model(model).is_err());
+    }
+
+    #[test]
+    fn recognises_the_ggml_header() {
+        assert!(has_ggml_magic(&[0x6c, 0x6d, 0x67, 0x67, 0x01, 0x00]));
+        assert!(has_ggml_magic(b"GGUF\x03\x00\x00\x00"));
+        assert!(!has_ggml_magic(b"PK\x03\x04 not a model"));
+        assert!(!has_ggml_magic(b"lmg"));
+        assert!(!has_ggml_magic(&[]));
+    }
+
+    #[test]
+    fn custom_model_file_needs_the_header() {
+        let journal = TestJournal::new();
+        let garbage = journal.outside("garbage.bin");
+        fs::write(&garbage, b"<html>Not Found</html>").unwrap();
+        let model = journal.outside("tiny-q5.bin");
+        fs::write(&model, b"lmgg small quantized model").unwrap();
+
+        assert!(set_model_from_file(garbage.to_string_lossy().into_owned()).is_err());
+        assert_eq!(get_active_model(), WhisperModel::BaseEn);
+
+        set_model_from_file(model.to_string_lossy().into_owned()).unwrap();
+        assert_eq!(get_active_model(), WhisperModel::Custom);
+        // A custom model is accepted by its header, however small
+        assert!(is_model_downloaded(WhisperModel::Custom));
+    }
+
+    #[test]
+    fn speed_is_a_moving_average() {
+        let second = Duration::from_secs(1);
+        let mut speed = SpeedTracker::default();
+
+        assert_eq!(speed.record(500, Duration::ZERO), 0);
+        assert_eq!(speed.record(1000, second), 1000);
+        assert_eq!(speed.record(3000, second), 2000);
+        assert_eq!(speed.record(1000, Duration::from_millis(500)), 2000);
+
+        // Only the last SPEED_WINDOW intervals count
+        for _ in 0..SPEED_WINDOW {
+            speed.record(4000, second);
+        }
+        assert_eq!(speed.record(4000, second), 4000);
+    }
+
+    #[test]
+    fn eta_and_percent() {
+        assert_eq!(eta_secs(10_000, 2000), 5);
+        assert_eq!(eta_secs(10_001, 2000), 6);
+        assert_eq!(eta_secs(10_000, 0), 0);
+
+        assert_eq!(download_percent(50, 200), 25);
+        assert_eq!(download_percent(300, 200), 100);
+        assert_eq!(download_percent(50, 0), 0);
+    }
+
+    #[test]
+    fn progress_is_reported_once_per_percent() {
+        let mut out = Vec::new();
+        let mut reported = Vec::new();
+
+        let downloaded =
+            tauri::async_runtime::block_on(write_chunks(chunks(), &mut out, 0, 100, |progress| {
+                reported.push((progress.percent, progress.downloaded))
+            }))
+            .unwrap();
+
+        assert_eq!(downloaded, 100);
+        assert_eq!(reported, [(25, 25), (50, 50), (75, 75), (100, 100)]);
+    }
+
+    #[test]
+    fn lists_every_model_once_smallest_first() {
+        let _journal = TestJournal::new();
+        let models = list_models().unwrap();
+
+        assert_eq!(models.len(), WhisperModel::ALL.len());
+        for model in WhisperModel::ALL {
+            assert_eq!(models.iter().filter(|s| s.model == model).count(), 1);
+        }
+        assert!(models
+            .windows(2)
+            .all(|pair| pair[0].approx_size_bytes <= pair[1].approx_size_bytes));
+        assert!(models.iter().all(|s| !s.downloaded));
+        assert!(models
+            .iter()
+            .all(|s| s.multilingual != s.model.is_english_only()));
+    }
+
+    #[test]
+    fn models_dir_setting_moves_the_model_path() {
+        let journal = TestJournal::new();
+        let before = get_model_path(WhisperModel::Base);
+        assert!(!before.starts_with(journal.root()));
+
+        let elsewhere = journal.outside("elsewhere");
+        set_models_dir(elsewhere.to_string_lossy().into_owned()).unwrap();
+        assert_eq!(
+            get_model_path(WhisperModel::Base),
+            elsewhere.join("ggml-base.bin")
+        );
+
+        // Clearing it goes back to the app-data default, outside the journal
+        set_models_dir(String::new()).unwrap();
+        assert_eq!(get_models_dir(), default_models_dir());
+    }
+
+    #[test]
+    fn legacy_models_are_moved_out_of_the_journal() {
+        let journal = TestJournal::new();
+        journal.write("models/ggml-tiny.bin", "lmgg");
+        journal.write("models/notes.txt", "keep me");
+
+        assert_eq!(migrate_legacy_models().unwrap(), 1);
+        assert!(get_model_path(WhisperModel::Tiny).is_file());
+        assert_eq!(journal.read("models/notes.txt"), "keep me");
+    }
+
+    #[test]
+    fn every_built_in_model_has_an_embedded_digest() {
+        for model in WhisperModel::ALL {
+            if model == WhisperModel::Custom {
+                continue;
+            }
+
+            let digest = embedded_checksum(model)
+                .unwrap_or_else(|| panic!("No SHA-256 embedded for {}", model));
+            assert_eq!(digest.len(), 64, "{}", model);
+            assert!(
+                digest
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)),
+                "{}",
+                model
+            );
+        }
+    }
 }
@@ -77,6 +77,7 @@ function App() {
   } = useVoiceRecording({
     onTranscription: handleTranscription,
     isModelReady,
+    entryFilename: selectedEntry,
   });
 
   // Audio import hook
@@ -87,6 +88,7 @@ function App() {
   } = useAudioImport({
     onTranscription: handleTranscription,
     isModelReady,
+    entryFilename: selectedEntry,
   });
 
   // Combined error message
@@ -5,11 +5,14 @@ import { open } from "@tauri-apps/plugin-dialog";
 interface UseAudioImportOptions {
   onTranscription: (text: string) => void;
   isModelReady: boolean;
+  // Entry the transcript goes into, stamped with the model used
+  entryFilename: string | null;
 }
 
 export function useAudioImport({
   onTranscription,
   isModelReady,
+  entryFilename,
 }: UseAudioImportOptions) {
   const [isImporting, setIsImporting] = useState(false);
   const [error, setError] = useState<string | null>(null);
@@ -44,6 +47,7 @@ export function useAudioImport({
       // Transcribe the file
       const transcript = await invoke<string>("transcribe_audio_file", {
         path: selected,
+        filename: entryFilename,
       });
 
       onTranscription(transcript);
@@ -52,7 +56,7 @@ export function useAudioImport({
     } finally {
       setIsImporting(false);
     }
-  }, [isModelReady, onTranscription]);
+  }, [isModelReady, onTranscription, entryFilename]);
 
   return {
     importAudioFile,
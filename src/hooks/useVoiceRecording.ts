@@ -7,11 +7,14 @@ export type RecordingState = "idle" | "recording" | "transcribing";
 interface UseVoiceRecordingOptions {
   onTranscription: (text: string) => void;
   isModelReady: boolean;
+  // Entry the transcript goes into, stamped with the model used
+  entryFilename: string | null;
 }
 
 export function useVoiceRecording({
   onTranscription,
   isModelReady,
+  entryFilename,
 }: UseVoiceRecordingOptions) {
   const [state, setState] = useState<RecordingState>("idle");
   const [error, setError] = useState<string | null>(null);
@@ -54,7 +57,9 @@ export function useVoiceRecording({
 
     try {
       setState("transcribing");
-      const transcript = await invoke<string>("stop_recording_and_transcribe");
+      const transcript = await invoke<string>("stop_recording_and_transcribe", {
+        filename: entryFilename,
+      });
       onTranscription(transcript);
       setState("idle");
       setRecordingDuration(0);
@@ -63,7 +68,7 @@ export function useVoiceRecording({
       setState("idle");
       setRecordingDuration(0);
     }
-  }, [state, onTranscription]);
+  }, [state, onTranscription, entryFilename]);
 
   // Cleanup timer on unmount
   useEffect(() => {
@@ -107,7 +112,9 @@ export function useVoiceRecording({
             }
             try {
               setState("transcribing");
-              const transcript = await invoke<string>("stop_recording_and_transcribe");
+              const transcript = await invoke<string>("stop_recording_and_transcribe", {
+                filename: entryFilename,
+              });
               onTranscription(transcript);
               setState("idle");
               setRecordingDuration(0);
@@ -131,7 +138,7 @@ export function useVoiceRecording({
         unregister(shortcut).catch(console.warn);
       }
     };
-  }, [isModelReady, onTranscription]);
+  }, [isModelReady, onTranscription, entryFilename]);
 
   // Format duration as mm:ss
   const formatDuration = (seconds: number): string => {
@@ -2,10 +2,20 @@ import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
+// Payload of the whisper-download-progress event
+export interface DownloadProgress {
+  percent: number;
+  downloaded: number;
+  total: number;
+  bytes_per_sec: number;
+  eta_secs: number;
+}
+
 export function useWhisperModel() {
   const [isModelReady, setIsModelReady] = useState(false);
   const [isDownloading, setIsDownloading] = useState(false);
   const [downloadProgress, setDownloadProgress] = useState(0);
+  const [downloadStats, setDownloadStats] = useState<DownloadProgress | null>(null);
   const [error, setError] = useState<string | null>(null);
 
   // Check if model is downloaded on mount
@@ -17,9 +27,10 @@ export function useWhisperModel() {
 
   // Listen for download progress events
   useEffect(() => {
-    const unlisten = listen<number>("whisper-download-progress", (event) => {
-      setDownloadProgress(event.payload);
-      if (event.payload >= 100) {
+    const unlisten = listen<DownloadProgress>("whisper-download-progress", (event) => {
+      setDownloadProgress(event.payload.percent);
+      setDownloadStats(event.payload);
+      if (event.payload.percent >= 100) {
         setIsModelReady(true);
         setIsDownloading(false);
       }
@@ -36,6 +47,7 @@ export function useWhisperModel() {
       setError(null);
       setIsDownloading(true);
       setDownloadProgress(0);
+      setDownloadStats(null);
 
       await invoke("download_whisper_model");
 
@@ -51,6 +63,7 @@ export function useWhisperModel() {
     isModelReady,
     isDownloading,
     downloadProgress,
+    downloadStats,
     downloadModel,
     error,
   };